@@ -0,0 +1,279 @@
+//! Procedural macros for [crabgrind](https://docs.rs/crabgrind), meant to be used through its
+//! re-exports.
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+type Error = (&'static str, Span);
+
+// documented at the `crabgrind` re-export
+#[proc_macro_attribute]
+pub fn profile(attr: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(attr, item) {
+        Ok(tokens) => tokens,
+        Err((msg, span)) => compile_error(msg, span),
+    }
+}
+
+// documented at the `crabgrind::dhat` re-export
+#[proc_macro]
+pub fn event(input: TokenStream) -> TokenStream {
+    match expand_event(input) {
+        Ok(tokens) => tokens,
+        Err((msg, span)) => compile_error(msg, span),
+    }
+}
+
+// Prefix of the functions generated by `event!`, followed by the label.
+const EVENT_PREFIX: &str = "__crabgrind_event_";
+
+// {
+//     #[inline(never)]
+//     fn __crabgrind_event_<label>(weight: ::core::option::Option<usize>) {
+//         ::core::hint::black_box("<label>");
+//         ::crabgrind::dhat::ad_hoc_event(weight);
+//     }
+//     __crabgrind_event_<label>(::core::convert::Into::into(<weight>))
+// }
+fn expand_event(input: TokenStream) -> Result<TokenStream, Error> {
+    let mut tokens = input.into_iter();
+
+    let label = match tokens.next() {
+        Some(TokenTree::Literal(lit)) => lit,
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::None => {
+            // a literal passed through a `macro_rules` fragment
+            match group.stream().into_iter().next() {
+                Some(TokenTree::Literal(lit)) => lit,
+                _ => return Err(("expected a string literal label", group.span())),
+            }
+        }
+        token => {
+            let span = token.map_or_else(Span::call_site, |token| token.span());
+            return Err(("expected a string literal label", span));
+        }
+    };
+    let text = label.to_string();
+    let text = text.strip_prefix('"').and_then(|text| text.strip_suffix('"'));
+    let Some(text) = text.filter(|text| {
+        !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }) else {
+        return Err(("the label must be a non-empty string of `[A-Za-z0-9_]`", label.span()));
+    };
+
+    let weight: TokenStream = match tokens.next() {
+        None => path(&["core", "option", "Option", "None"]).into_iter().collect(),
+        Some(TokenTree::Punct(comma)) if comma.as_char() == ',' => tokens.collect(),
+        Some(token) => return Err(("expected `,`", token.span())),
+    };
+    if weight.is_empty() {
+        return Err(("expected a weight after `,`", label.span()));
+    }
+
+    let name = Ident::new(&format!("{EVENT_PREFIX}{text}"), Span::call_site());
+    let call = |path: &[&str], args: TokenStream| {
+        let mut tokens = self::path(path);
+        tokens.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, args)));
+        tokens
+    };
+
+    let mut body = call(&["core", "hint", "black_box"], TokenTree::Literal(label).into());
+    body.push(TokenTree::Punct(Punct::new(';', Spacing::Alone)));
+    body.extend(call(&["crabgrind", "dhat", "ad_hoc_event"], ident("weight").into()));
+    body.push(TokenTree::Punct(Punct::new(';', Spacing::Alone)));
+
+    let mut params = vec![ident("weight"), TokenTree::Punct(Punct::new(':', Spacing::Alone))];
+    params.extend(path(&["core", "option", "Option"]));
+    params.extend([TokenTree::Punct(Punct::new('<', Spacing::Alone)), ident("usize")]);
+    params.push(TokenTree::Punct(Punct::new('>', Spacing::Alone)));
+
+    let mut block = vec![
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            [
+                ident("inline"),
+                TokenTree::Group(Group::new(Delimiter::Parenthesis, ident("never").into())),
+            ]
+            .into_iter()
+            .collect(),
+        )),
+        ident("fn"),
+        TokenTree::Ident(name.clone()),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, params.into_iter().collect())),
+        TokenTree::Group(Group::new(Delimiter::Brace, body.into_iter().collect())),
+        TokenTree::Ident(name),
+    ];
+    let weight = call(&["core", "convert", "Into", "into"], weight);
+    block.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, weight.into_iter().collect())));
+
+    Ok(TokenTree::Group(Group::new(Delimiter::Brace, block.into_iter().collect())).into())
+}
+
+fn ident(name: &str) -> TokenTree {
+    TokenTree::Ident(Ident::new(name, Span::call_site()))
+}
+
+fn expand(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+    let mut item: Vec<TokenTree> = item.into_iter().collect();
+
+    let name = function_name(&item)?;
+    let Some(TokenTree::Group(body)) = item.pop() else {
+        return Err(("expected a function with a body", Span::call_site()));
+    };
+    let span = body.span();
+    if body.delimiter() != Delimiter::Brace {
+        return Err(("expected a function with a body", span));
+    }
+
+    let (dump, zero) = parse_args(attr)?;
+    let dump = dump.unwrap_or_else(|| Literal::string(name.trim_start_matches("r#")));
+    let zero = zero.unwrap_or_else(|| Ident::new("false", Span::call_site()));
+
+    // ::crabgrind::__profile!(<dump>, <zero>, { <body> })
+    let args = [
+        TokenTree::Literal(dump),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        TokenTree::Ident(zero),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        TokenTree::Group(body),
+    ];
+    let mut invocation = path(&["crabgrind", "__profile"]);
+    invocation.extend([
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, args.into_iter().collect())),
+    ]);
+
+    let mut new_body = Group::new(Delimiter::Brace, invocation.into_iter().collect());
+    new_body.set_span(span);
+    item.push(TokenTree::Group(new_body));
+
+    Ok(item.into_iter().collect())
+}
+
+// Name of the function, rejecting `async` ones.
+fn function_name(item: &[TokenTree]) -> Result<String, Error> {
+    let mut tokens = item.iter();
+
+    while let Some(token) = tokens.next() {
+        let TokenTree::Ident(ident) = token else { continue };
+
+        match ident.to_string().as_str() {
+            "async" => return Err(("`async` functions are not supported", ident.span())),
+            "fn" => match tokens.next() {
+                Some(TokenTree::Ident(name)) => return Ok(name.to_string()),
+                _ => break,
+            },
+            _ => {}
+        }
+    }
+
+    Err(("expected a function", Span::call_site()))
+}
+
+// `dump = "<reason>", zero = <bool>`, both optional.
+fn parse_args(attr: TokenStream) -> Result<(Option<Literal>, Option<Ident>), Error> {
+    let (mut dump, mut zero) = (None, None);
+    let mut tokens = attr.into_iter();
+
+    while let Some(token) = tokens.next() {
+        let TokenTree::Ident(key) = token else {
+            return Err(("expected `dump = \"..\"` or `zero = <bool>`", token.span()));
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(eq)) if eq.as_char() == '=' => {}
+            _ => return Err(("expected `=`", key.span())),
+        }
+        let value = tokens.next().ok_or(("expected a value", key.span()))?;
+
+        match (key.to_string().as_str(), value) {
+            ("dump", TokenTree::Literal(lit)) if dump.is_none() => {
+                validate_reason(&lit)?;
+                dump = Some(lit);
+            }
+            ("zero", TokenTree::Ident(b)) if zero.is_none() => {
+                if !matches!(b.to_string().as_str(), "true" | "false") {
+                    return Err(("`zero` expects `true` or `false`", b.span()));
+                }
+                zero = Some(b);
+            }
+            ("dump" | "zero", value) => return Err(("invalid or repeated argument", value.span())),
+            _ => return Err(("unknown argument, expected `dump` or `zero`", key.span())),
+        }
+
+        match tokens.next() {
+            None => break,
+            Some(TokenTree::Punct(comma)) if comma.as_char() == ',' => {}
+            Some(token) => return Err(("expected `,`", token.span())),
+        }
+    }
+
+    Ok((dump, zero))
+}
+
+// The dump reason becomes a C string, so it must be a string literal without nul bytes.
+fn validate_reason(lit: &Literal) -> Result<(), Error> {
+    let text = lit.to_string();
+    let err = Err(("`dump` expects a string literal without nul bytes", lit.span()));
+
+    if text.starts_with('r') {
+        return if text.contains('\0') { err } else { Ok(()) };
+    }
+    let Some(text) = text.strip_prefix('"').and_then(|text| text.strip_suffix('"')) else {
+        return err;
+    };
+
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let nul = match c {
+            '\0' => true,
+            '\\' => match chars.next() {
+                Some('0') => true,
+                Some('x') => chars.by_ref().take(2).eq("00".chars()),
+                Some('u') => {
+                    let digits: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    digits.trim_start_matches('{').trim_start_matches('0').is_empty()
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if nul {
+            return err;
+        }
+    }
+
+    Ok(())
+}
+
+// `::a::b`
+fn path(segments: &[&str]) -> Vec<TokenTree> {
+    segments
+        .iter()
+        .flat_map(|segment| {
+            [
+                TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                TokenTree::Ident(Ident::new(segment, Span::call_site())),
+            ]
+        })
+        .collect()
+}
+
+// `::core::compile_error!("<msg>")`
+fn compile_error(msg: &str, span: Span) -> TokenStream {
+    let mut tokens = path(&["core", "compile_error"]);
+    tokens.extend([
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenTree::Literal(Literal::string(msg)).into(),
+        )),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
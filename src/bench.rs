@@ -0,0 +1,526 @@
+#![doc = include_str!("../doc/bench.md")]
+use crate::{cachegrind, callgrind, valgrind::ToolConfig};
+
+use core::{
+    fmt::{self, Display},
+    hint::black_box,
+    ops::Index,
+    str::FromStr,
+};
+use std::{
+    boxed::Box,
+    env,
+    ffi::CString,
+    format, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    println, process,
+    string::String,
+    vec,
+    vec::Vec,
+};
+
+/// Environment variable holding the name of the benchmark run by the re-executed process.
+pub const BENCH_ENV: &str = "CRABGRIND_BENCH";
+/// Environment variable forcing the baseline file to be overwritten with the current results.
+pub const SAVE_BASELINE_ENV: &str = "CRABGRIND_SAVE_BASELINE";
+
+/// Valgrind tool measuring the benchmarks.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Tool {
+    /// The region is isolated with `zero_stats`/`toggle_collect`/`dump_stats(name)`.
+    #[default]
+    Callgrind,
+    /// The region is isolated with `start_instrumentation`/`stop_instrumentation`, requires
+    /// Valgrind **3.22** or higher.
+    Cachegrind,
+}
+
+/// Simulated events, named after Valgrind's.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Event {
+    /// Instructions executed.
+    Ir,
+    /// Memory reads.
+    Dr,
+    /// Memory writes.
+    Dw,
+    /// L1 instruction cache read misses.
+    I1mr,
+    /// L1 data cache read misses.
+    D1mr,
+    /// L1 data cache write misses.
+    D1mw,
+    /// Last-level cache instruction read misses.
+    ILmr,
+    /// Last-level cache data read misses.
+    DLmr,
+    /// Last-level cache data write misses.
+    DLmw,
+}
+
+impl Event {
+    /// All the events, in reporting order.
+    pub const ALL: [Event; 9] = [
+        Self::Ir,
+        Self::Dr,
+        Self::Dw,
+        Self::I1mr,
+        Self::D1mr,
+        Self::D1mw,
+        Self::ILmr,
+        Self::DLmr,
+        Self::DLmw,
+    ];
+
+    /// Event name, as written by Valgrind.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Ir => "Ir",
+            Self::Dr => "Dr",
+            Self::Dw => "Dw",
+            Self::I1mr => "I1mr",
+            Self::D1mr => "D1mr",
+            Self::D1mw => "D1mw",
+            Self::ILmr => "ILmr",
+            Self::DLmr => "DLmr",
+            Self::DLmw => "DLmw",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.name() == name)
+    }
+}
+
+/// Event counts of a benchmark, indexed by [`Event`].
+///
+/// Events not simulated by the tool are zero.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Costs([u64; 9]);
+
+impl Costs {
+    /// L1 cache misses, instruction and data.
+    pub fn l1_misses(&self) -> u64 {
+        self[Event::I1mr] + self[Event::D1mr] + self[Event::D1mw]
+    }
+
+    /// Last-level cache misses, instruction and data.
+    pub fn ll_misses(&self) -> u64 {
+        self[Event::ILmr] + self[Event::DLmr] + self[Event::DLmw]
+    }
+
+    // Costs of the known events, in the tool output columns order.
+    fn from_columns(events: &[String], costs: &[u64]) -> Self {
+        let mut known = Self::default();
+        for (name, &cost) in events.iter().zip(costs) {
+            if let Some(event) = Event::from_name(name) {
+                known.0[event as usize] = cost;
+            }
+        }
+        known
+    }
+}
+
+impl Index<Event> for Costs {
+    type Output = u64;
+
+    #[inline(always)]
+    fn index(&self, event: Event) -> &u64 {
+        &self.0[event as usize]
+    }
+}
+
+// `Ir=1 Dr=2 ...`, the baseline file format
+impl Display for Costs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, event) in Event::ALL.into_iter().enumerate() {
+            let sep = if i == 0 { "" } else { " " };
+            write!(f, "{sep}{}={}", event.name(), self[event])?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Costs {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut costs = Self::default();
+        for pair in s.split_whitespace() {
+            let (name, count) = pair.split_once('=').ok_or(())?;
+            let event = Event::from_name(name).ok_or(())?;
+            costs.0[event as usize] = count.parse().map_err(|_| ())?;
+        }
+        Ok(costs)
+    }
+}
+
+/// Result of a single benchmark.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BenchResult {
+    /// Benchmark name.
+    pub name: String,
+    /// Measured costs.
+    pub costs: Costs,
+    /// Costs stored in the baseline, if any.
+    pub baseline: Option<Costs>,
+}
+
+/// Results of all the benchmarks.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Hash)]
+pub struct Report {
+    /// Results in the order the benchmarks were added.
+    pub results: Vec<BenchResult>,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            writeln!(f, "{}", result.name)?;
+
+            for event in Event::ALL {
+                let count = result.costs[event];
+                write!(f, "  {:<5}{count:>16}", event.name())?;
+
+                match result.baseline.map(|baseline| baseline[event]) {
+                    Some(base) if base != count => {
+                        writeln!(f, " ({:+.2}%)", change(base, count))?;
+                    }
+                    Some(_) => writeln!(f, " (no change)")?,
+                    None => writeln!(f)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Event count which exceeded the regression threshold.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Regression {
+    /// Benchmark name.
+    pub name: String,
+    /// The regressed event.
+    pub event: Event,
+    /// Count stored in the baseline.
+    pub baseline: u64,
+    /// Measured count.
+    pub current: u64,
+}
+
+/// Benchmark harness errors.
+#[derive(Debug)]
+pub enum BenchError {
+    /// The benchmark name is empty, or contains whitespaces, path separators or nul bytes.
+    InvalidName(String),
+    /// The benchmark process failed, holds its `stderr`.
+    Failed {
+        /// Benchmark name.
+        name: String,
+        /// Valgrind log and the benchmark output.
+        stderr: String,
+    },
+    /// The tool output file is missing or malformed.
+    NoOutput(String),
+    /// Some event counts exceeded the regression threshold.
+    Regression(Vec<Regression>),
+    /// Valgrind could not be started, or the output or baseline files could not be accessed.
+    Io(io::Error),
+}
+
+type Routine = Box<dyn Fn()>;
+
+#[doc = include_str!("../doc/bench/Bench.md")]
+pub struct Bench {
+    tool: Tool,
+    benches: Vec<(String, Routine)>,
+    baseline: Option<PathBuf>,
+    threshold: f64,
+    events: Vec<Event>,
+    flags: Vec<String>,
+}
+
+impl Bench {
+    /// Harness measuring with the `tool`.
+    pub fn new(tool: Tool) -> Self {
+        Self {
+            tool,
+            benches: Vec::new(),
+            baseline: None,
+            threshold: 1.0,
+            events: vec![Event::Ir],
+            flags: Vec::new(),
+        }
+    }
+
+    /// Adds the benchmark, measuring a whole `routine` call.
+    #[must_use]
+    pub fn bench<R>(self, name: impl Into<String>, routine: impl Fn() -> R + 'static) -> Self {
+        self.bench_with(name, || (), move |()| routine())
+    }
+
+    /// Adds the benchmark, measuring only the `routine`, not the `setup` of its input.
+    ///
+    /// Dropping of the `routine` output is not measured either.
+    #[must_use]
+    pub fn bench_with<T, R>(
+        mut self,
+        name: impl Into<String>,
+        setup: impl Fn() -> T + 'static,
+        routine: impl Fn(T) -> R + 'static,
+    ) -> Self {
+        let tool = self.tool;
+        let name = name.into();
+        let reason = CString::new(name.clone()).unwrap_or_default();
+
+        let measured = move || {
+            let input = setup();
+
+            let output = match tool {
+                Tool::Callgrind => {
+                    callgrind::zero_stats();
+                    callgrind::toggle_collect();
+                    let output = routine(black_box(input));
+                    callgrind::toggle_collect();
+                    callgrind::dump_stats(reason.as_c_str());
+                    output
+                }
+                Tool::Cachegrind => {
+                    cachegrind::start_instrumentation();
+                    let output = routine(black_box(input));
+                    cachegrind::stop_instrumentation();
+                    output
+                }
+            };
+
+            drop(black_box(output));
+        };
+
+        self.benches.push((name, Box::new(measured)));
+        self
+    }
+
+    /// Compares the results with the baseline file, which is created if it doesn't exist, or
+    /// if [`SAVE_BASELINE_ENV`] is set.
+    #[must_use]
+    pub fn baseline(mut self, path: impl Into<PathBuf>) -> Self {
+        self.baseline = Some(path.into());
+        self
+    }
+
+    /// Sets the allowed growth of the compared events over the baseline, in percent, `1.0` by
+    /// default.
+    #[must_use]
+    pub fn threshold(mut self, percent: f64) -> Self {
+        self.threshold = percent;
+        self
+    }
+
+    /// Sets the events compared with the baseline, [`Event::Ir`] by default.
+    #[must_use]
+    pub fn events(mut self, events: impl IntoIterator<Item = Event>) -> Self {
+        self.events = events.into_iter().collect();
+        self
+    }
+
+    /// Adds the Valgrind core or tool flag, e.g. cache geometry `--D1=65536,2,64`.
+    #[must_use]
+    pub fn flag(mut self, flag: impl Into<String>) -> Self {
+        self.flags.push(flag.into());
+        self
+    }
+
+    #[doc = include_str!("../doc/bench/run.md")]
+    pub fn run(self) -> Result<Report, BenchError> {
+        if let Some((name, _)) = self.benches.iter().find(|(name, _)| !valid_name(name)) {
+            return Err(BenchError::InvalidName(name.clone()));
+        }
+        if !cfg!(feature = "valgrind") {
+            return Ok(Report::default());
+        }
+
+        // re-executed process, run the benchmark and exit
+        if let Ok(name) = env::var(BENCH_ENV) {
+            if let Some((_, routine)) = self.benches.iter().find(|(n, _)| *n == name) {
+                routine();
+                process::exit(0);
+            }
+            return Err(BenchError::NoOutput(name));
+        }
+
+        let dir = env::temp_dir().join(format!("crabgrind-bench.{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        let results = self.measure_all(&dir);
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut report = Report { results: results? };
+        let regressions = match &self.baseline {
+            Some(path) => self.compare(&mut report, path)?,
+            None => Vec::new(),
+        };
+
+        println!("{report}");
+
+        if regressions.is_empty() { Ok(report) } else { Err(BenchError::Regression(regressions)) }
+    }
+
+    fn measure_all(&self, dir: &Path) -> Result<Vec<BenchResult>, BenchError> {
+        self.benches
+            .iter()
+            .map(|(name, _)| {
+                Ok(BenchResult {
+                    name: name.clone(),
+                    costs: self.measure(name, dir)?,
+                    baseline: None,
+                })
+            })
+            .collect()
+    }
+
+    fn measure(&self, name: &str, dir: &Path) -> Result<Costs, BenchError> {
+        // each benchmark runs in a separate process, no need for `%p`
+        let out_file = dir.join(name);
+        let config = match self.tool {
+            Tool::Callgrind => ToolConfig::new("callgrind").flags([
+                format!("--callgrind-out-file={}", out_file.display()),
+                "--collect-atstart=no".into(),
+            ]),
+            Tool::Cachegrind => ToolConfig::new("cachegrind").flags([
+                format!("--cachegrind-out-file={}", out_file.display()),
+                "--instr-atstart=no".into(),
+            ]),
+        };
+
+        let output = config
+            .flags(["--cache-sim=yes"])
+            .flags(&self.flags)
+            .command()?
+            .env(BENCH_ENV, name)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+            return Err(BenchError::Failed { name: name.into(), stderr });
+        }
+
+        let no_output = || BenchError::NoOutput(name.into());
+        match self.tool {
+            // the dump is a separate part, next to the final one
+            Tool::Callgrind => {
+                let profile =
+                    callgrind::format::Profile::read_dumps(&out_file).map_err(|_| no_output())?;
+                let part = profile.dump(name).ok_or_else(no_output)?;
+                let totals = if part.totals.is_empty() { &part.summary } else { &part.totals };
+
+                Ok(Costs::from_columns(&part.events, totals))
+            }
+            Tool::Cachegrind => {
+                let profile =
+                    cachegrind::format::Profile::read(&out_file).map_err(|_| no_output())?;
+
+                Ok(Costs::from_columns(&profile.events, &profile.summary))
+            }
+        }
+    }
+
+    // Fills in the baseline costs and returns the regressions, writes the baseline file if it's
+    // missing or forced to.
+    fn compare(&self, report: &mut Report, path: &Path) -> Result<Vec<Regression>, BenchError> {
+        if env::var_os(SAVE_BASELINE_ENV).is_some() || !path.exists() {
+            let mut file = io::BufWriter::new(fs::File::create(path)?);
+            for result in &report.results {
+                writeln!(file, "{} {}", result.name, result.costs)?;
+            }
+            file.flush()?;
+            return Ok(Vec::new());
+        }
+
+        let baseline = fs::read_to_string(path)?;
+        let mut regressions = Vec::new();
+
+        for result in &mut report.results {
+            result.baseline = baseline.lines().find_map(|line| {
+                let (name, costs) = line.split_once(' ')?;
+                (name == result.name).then(|| costs.parse().ok())?
+            });
+            let Some(base) = result.baseline else { continue };
+
+            for &event in &self.events {
+                let (baseline, current) = (base[event], result.costs[event]);
+                if change(baseline, current) > self.threshold {
+                    regressions.push(Regression {
+                        name: result.name.clone(),
+                        event,
+                        baseline,
+                        current,
+                    });
+                }
+            }
+        }
+
+        Ok(regressions)
+    }
+}
+
+impl Default for Bench {
+    fn default() -> Self {
+        Self::new(Tool::default())
+    }
+}
+
+impl fmt::Debug for Bench {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bench")
+            .field("tool", &self.tool)
+            .field("benches", &self.benches.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .field("baseline", &self.baseline)
+            .field("threshold", &self.threshold)
+            .field("events", &self.events)
+            .field("flags", &self.flags)
+            .finish()
+    }
+}
+
+// Benchmark names are used as file names and as a command line argument.
+fn valid_name(name: &str) -> bool {
+    let valid = |c: char| !(c.is_whitespace() || c == '/' || c == '\\' || c == '\0');
+    !name.is_empty() && name.chars().all(valid)
+}
+
+// Relative change in percent, growth from zero is infinite.
+#[allow(clippy::cast_precision_loss)]
+fn change(baseline: u64, current: u64) -> f64 {
+    match (baseline, current) {
+        (0, 0) => 0.0,
+        (0, _) => f64::INFINITY,
+        _ => (current as f64 - baseline as f64) / baseline as f64 * 100.0,
+    }
+}
+
+impl From<io::Error> for BenchError {
+    #[inline(always)]
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "invalid benchmark name {name:?}"),
+            Self::Failed { name, stderr } => write!(f, "benchmark '{name}' failed:\n{stderr}"),
+            Self::NoOutput(name) => write!(f, "no tool output for benchmark '{name}'"),
+            Self::Regression(regressions) => {
+                write!(f, "regression threshold exceeded:")?;
+                for Regression { name, event, baseline, current } in regressions {
+                    write!(f, "\n  {name} {}: {baseline} -> {current}", event.name())?;
+                }
+                Ok(())
+            }
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for BenchError {}
@@ -3,13 +3,28 @@
 #![cfg_attr(not(feature = "valgrind"), allow(unused, missing_docs, clippy::needless_pass_by_value))]
 #![no_std]
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 #[cfg(feature = "opt-out")]
 compile_error!("`opt-out` was removed (v0.3). Use `default-features = false`.");
 
+#[cfg(feature = "std")]
+pub mod bench;
 #[cfg(feature = "valgrind")]
 mod bindings;
 mod requests;
-pub use requests::{ScopeGuard, cachegrind, callgrind, dhat, drd, helgrind, memcheck, valgrind};
+#[cfg(target_has_atomic = "8")]
+pub use requests::race;
+pub use requests::{
+    ScopeGuard, cachegrind, callgrind, dhat, drd, helgrind, massif, memcheck, valgrind,
+};
+#[cfg(target_has_atomic = "ptr")]
+pub mod sync;
+
+#[doc = include_str!("../doc/profile.md")]
+pub use crabgrind_macros::profile;
 
 /// Valgrind version this crate was compiled against.
 pub const VALGRIND_VERSION: (u32, u32) = imp::VALGRIND_VERSION;
@@ -1,9 +1,49 @@
 #![doc = include_str!("../../doc/cachegrind.md")]
 use super::client_request;
+#[cfg(target_has_atomic = "ptr")]
+use crate::requests::SharedDepth;
+use crate::{
+    ScopeGuard,
+    requests::{Scope, sealed::Sealed},
+};
 
 #[cfg(feature = "valgrind")]
 use crate::bindings::CG_CachegrindClientRequest as CR;
 
+#[cfg(feature = "std")]
+pub mod format;
+
+// Instrumentation state is process-wide
+#[cfg(target_has_atomic = "ptr")]
+static INSTRUMENT_DEPTH: SharedDepth = SharedDepth::new();
+
+#[doc(hidden)]
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct Instrumenting;
+
+#[cfg(target_has_atomic = "ptr")]
+impl Scope for Instrumenting {
+    type Inner = ();
+
+    #[inline(always)]
+    fn enter((): Self::Inner) {
+        INSTRUMENT_DEPTH.enter(start_instrumentation);
+    }
+
+    #[inline(always)]
+    fn exit((): Self::Inner) {
+        INSTRUMENT_DEPTH.exit(stop_instrumentation);
+    }
+}
+
+#[doc = include_str!("../../doc/cachegrind/instrument.md")]
+#[cfg(target_has_atomic = "ptr")]
+#[inline(always)]
+pub fn instrument() -> ScopeGuard<Instrumenting> {
+    ScopeGuard::new(())
+}
+
 #[doc = include_str!("../../doc/cachegrind/start_instrumentation.md")]
 #[inline(always)]
 pub fn start_instrumentation() {
@@ -15,3 +55,6 @@ pub fn start_instrumentation() {
 pub fn stop_instrumentation() {
     client_request!(CR::CG_CACHEGRIND_STOP_INSTRUMENTATION);
 }
+
+#[cfg(target_has_atomic = "ptr")]
+impl Sealed for Instrumenting {}
@@ -0,0 +1,300 @@
+#![doc = include_str!("../../../doc/cachegrind/format.md")]
+use std::{
+    borrow::ToOwned,
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+    string::String,
+    vec::Vec,
+};
+
+/// Profile data of a Cachegrind output file.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Profile {
+    /// Free-form descriptions (`desc:`), e.g. the simulated cache geometry.
+    pub desc: Vec<String>,
+    /// Profiled command line (`cmd:`).
+    pub cmd: Option<String>,
+    /// Event names (`events:`), the order of the cost columns.
+    pub events: Vec<String>,
+    /// Total costs (`summary:`).
+    pub summary: Vec<u64>,
+    /// Functions with costs, in the order of appearance.
+    pub functions: Vec<Function>,
+}
+
+/// Function cost center.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Function {
+    /// Source file (`fl=`).
+    pub file: String,
+    /// Function name (`fn=`).
+    pub name: String,
+    /// Costs of the function, per event.
+    pub costs: Vec<u64>,
+    /// Costs per source line.
+    pub lines: BTreeMap<u64, Vec<u64>>,
+}
+
+/// Difference between two profiles, as computed by `cg_diff`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Diff {
+    /// Event names, the order of the cost columns.
+    pub events: Vec<String>,
+    /// Difference of the total costs.
+    pub summary: Vec<i64>,
+    /// Functions with changed costs.
+    pub functions: Vec<FunctionDiff>,
+}
+
+/// Difference of a function costs.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct FunctionDiff {
+    /// Source file.
+    pub file: String,
+    /// Function name.
+    pub name: String,
+    /// Costs difference, per event.
+    pub costs: Vec<i64>,
+}
+
+/// Profile parsing and combining errors.
+#[derive(Debug)]
+pub enum FormatError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// Malformed line.
+    Syntax {
+        /// Line number, starting at 1.
+        line: usize,
+        /// What's wrong with it.
+        reason: &'static str,
+    },
+    /// The profiles were collected with different events.
+    EventMismatch,
+}
+
+impl Profile {
+    /// Parses the profile data file contents.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Syntax`] - The text is not a valid profile.
+    pub fn parse(text: &str) -> Result<Self, FormatError> {
+        let mut profile = Self::default();
+        let mut keys = HashMap::new();
+        let (mut file, mut function) = (String::new(), None);
+
+        for (i, line) in text.lines().enumerate() {
+            let syntax = |reason| FormatError::Syntax { line: i + 1, reason };
+            let line = line.trim_end();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.as_bytes()[0].is_ascii_digit() {
+                let index = function.ok_or(syntax("costs outside of a function"))?;
+                let mut numbers = line.split_whitespace().map(|n| n.parse::<u64>().ok());
+
+                let line_number = numbers.next().flatten().ok_or(syntax("invalid line number"))?;
+                let mut costs: Vec<u64> =
+                    numbers.collect::<Option<_>>().ok_or(syntax("invalid cost"))?;
+                costs.resize(costs.len().max(profile.events.len()), 0);
+
+                let function: &mut Function = &mut profile.functions[index];
+                add(&mut function.costs, &costs);
+                add(function.lines.entry(line_number).or_default(), &costs);
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                match key {
+                    // `fi`/`fe` are written by versions before 3.21 for inlined code
+                    "fl" | "fi" | "fe" => file = value.into(),
+                    "fn" => {
+                        let key = (file.clone(), value.to_owned());
+                        function = Some(*keys.entry(key).or_insert_with(|| {
+                            profile.functions.push(Function {
+                                file: file.clone(),
+                                name: value.into(),
+                                ..Function::default()
+                            });
+                            profile.functions.len() - 1
+                        }));
+                    }
+                    _ => return Err(syntax("unknown specification")),
+                }
+                continue;
+            }
+
+            let (key, value) = line.split_once(':').ok_or(syntax("unrecognized line"))?;
+            let value = value.trim();
+            match key {
+                "desc" => profile.desc.push(value.into()),
+                "cmd" => profile.cmd = Some(value.into()),
+                "events" => {
+                    profile.events = value.split_whitespace().map(ToOwned::to_owned).collect();
+                }
+                "summary" => {
+                    profile.summary = value
+                        .split_whitespace()
+                        .map(|n| n.parse().ok())
+                        .collect::<Option<_>>()
+                        .ok_or(syntax("invalid summary"))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Reads and parses the profile data file.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Io`] - The file could not be read.
+    /// - [`FormatError::Syntax`] - The file is not a valid profile.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Column of the event in the cost vectors.
+    pub fn event(&self, name: &str) -> Option<usize> {
+        self.events.iter().position(|event| event == name)
+    }
+
+    /// First function with the `name`.
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Cost of the `event` summed over all the functions with the `name`, `None` if the event
+    /// is unknown.
+    pub fn cost(&self, name: &str, event: &str) -> Option<u64> {
+        let column = self.event(event)?;
+        let functions = self.functions.iter().filter(|function| function.name == name);
+
+        Some(functions.map(|function| function.costs.get(column).copied().unwrap_or(0)).sum())
+    }
+
+    #[doc = include_str!("../../../doc/cachegrind/format/diff.md")]
+    pub fn diff(&self, after: &Profile) -> Result<Diff, FormatError> {
+        if self.events != after.events {
+            return Err(FormatError::EventMismatch);
+        }
+
+        let mut costs: BTreeMap<(&str, &str), Vec<i64>> = BTreeMap::new();
+        for (sign, profile) in [(-1, self), (1, after)] {
+            for function in &profile.functions {
+                let acc = costs.entry((&function.file, &function.name)).or_default();
+                acc.resize(acc.len().max(function.costs.len()), 0);
+
+                for (acc, &cost) in acc.iter_mut().zip(&function.costs) {
+                    *acc += sign * signed(cost);
+                }
+            }
+        }
+
+        let summary = (0..self.events.len())
+            .map(|i| {
+                let cost = |summary: &[u64]| signed(summary.get(i).copied().unwrap_or(0));
+                cost(&after.summary) - cost(&self.summary)
+            })
+            .collect();
+        let functions = costs
+            .into_iter()
+            .filter(|(_, costs)| costs.iter().any(|&cost| cost != 0))
+            .map(|((file, name), costs)| FunctionDiff {
+                file: file.into(),
+                name: name.into(),
+                costs,
+            })
+            .collect();
+
+        Ok(Diff { events: self.events.clone(), summary, functions })
+    }
+
+    #[doc = include_str!("../../../doc/cachegrind/format/merge.md")]
+    pub fn merge(&mut self, other: &Profile) -> Result<(), FormatError> {
+        if self.events != other.events {
+            return Err(FormatError::EventMismatch);
+        }
+
+        let mut keys: HashMap<(String, String), usize> = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(i, function)| ((function.file.clone(), function.name.clone()), i))
+            .collect();
+
+        for function in &other.functions {
+            let key = (function.file.clone(), function.name.clone());
+            let Some(&index) = keys.get(&key) else {
+                keys.insert(key, self.functions.len());
+                self.functions.push(function.clone());
+                continue;
+            };
+
+            let merged = &mut self.functions[index];
+            add(&mut merged.costs, &function.costs);
+            for (line, costs) in &function.lines {
+                add(merged.lines.entry(*line).or_default(), costs);
+            }
+        }
+        add(&mut self.summary, &other.summary);
+
+        Ok(())
+    }
+}
+
+impl Diff {
+    /// Column of the event in the cost vectors.
+    pub fn event(&self, name: &str) -> Option<usize> {
+        self.events.iter().position(|event| event == name)
+    }
+
+    /// Change of the `event` summed over all the functions with the `name`, `None` if the event is
+    /// unknown. Unchanged functions are not in the diff, so their change is zero.
+    pub fn cost(&self, name: &str, event: &str) -> Option<i64> {
+        let column = self.event(event)?;
+        let functions = self.functions.iter().filter(|function| function.name == name);
+
+        Some(functions.map(|function| function.costs.get(column).copied().unwrap_or(0)).sum())
+    }
+}
+
+// Costs fit in `i64` for any realistic profile.
+#[allow(clippy::cast_possible_wrap)]
+fn signed(cost: u64) -> i64 {
+    cost as i64
+}
+
+fn add(acc: &mut Vec<u64>, costs: &[u64]) {
+    if acc.len() < costs.len() {
+        acc.resize(costs.len(), 0);
+    }
+    for (acc, cost) in acc.iter_mut().zip(costs) {
+        *acc += cost;
+    }
+}
+
+impl From<io::Error> for FormatError {
+    #[inline(always)]
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl core::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Syntax { line, reason } => write!(f, "line {line}: {reason}"),
+            Self::EventMismatch => write!(f, "profiles have different events"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
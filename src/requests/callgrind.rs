@@ -1,10 +1,116 @@
 #![doc = include_str!("../../doc/callgrind.md")]
 use super::client_request;
+#[cfg(target_has_atomic = "ptr")]
+use crate::requests::SharedDepth;
+use crate::{
+    ScopeGuard,
+    requests::{Scope, sealed::Sealed},
+};
 
 #[cfg(feature = "valgrind")]
 use crate::bindings::CG_CallgrindClientRequest as CR;
 
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod format;
+pub mod monitor;
+#[cfg(all(feature = "signals", unix))]
+mod signal;
+
+#[cfg(all(feature = "signals", unix))]
+pub use signal::install_signal_handlers;
+
+#[cfg(feature = "std")]
+use core::cell::Cell;
 use core::ffi::CStr;
+use core::fmt;
+use core::marker::PhantomData;
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    // Collection state is per-thread in Callgrind
+    static COLLECT_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// Instrumentation state is process-wide
+#[cfg(target_has_atomic = "ptr")]
+static INSTRUMENT_DEPTH: SharedDepth = SharedDepth::new();
+
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[derive(Debug)]
+// `!Send`, the nesting depth is thread-local
+pub struct Collecting(PhantomData<*const ()>);
+
+#[cfg(feature = "std")]
+impl Scope for Collecting {
+    type Inner = ();
+
+    #[inline(always)]
+    fn enter((): Self::Inner) {
+        if COLLECT_DEPTH.with(|depth| depth.replace(depth.get() + 1)) == 0 {
+            toggle_collect();
+        }
+    }
+
+    #[inline(always)]
+    fn exit((): Self::Inner) {
+        if COLLECT_DEPTH.with(|depth| depth.replace(depth.get() - 1)) == 1 {
+            toggle_collect();
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Profiling(PhantomData<*const ()>);
+
+impl Scope for Profiling {
+    type Inner = (&'static CStr, bool);
+
+    #[inline(always)]
+    fn enter((_, zero): Self::Inner) {
+        if zero {
+            zero_stats();
+        }
+
+        #[cfg(feature = "std")]
+        Collecting::enter(());
+        #[cfg(not(feature = "std"))]
+        toggle_collect();
+    }
+
+    #[inline(always)]
+    fn exit((reason, _): Self::Inner) {
+        #[cfg(feature = "std")]
+        Collecting::exit(());
+        #[cfg(not(feature = "std"))]
+        toggle_collect();
+
+        dump_stats(reason);
+    }
+}
+
+#[doc(hidden)]
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct Instrumenting;
+
+#[cfg(target_has_atomic = "ptr")]
+impl Scope for Instrumenting {
+    type Inner = ();
+
+    #[inline(always)]
+    fn enter((): Self::Inner) {
+        INSTRUMENT_DEPTH.enter(start_instrumentation);
+    }
+
+    #[inline(always)]
+    fn exit((): Self::Inner) {
+        INSTRUMENT_DEPTH.exit(stop_instrumentation);
+    }
+}
 
 #[doc = include_str!("../../doc/callgrind/dump_stats.md")]
 #[inline(always)]
@@ -16,6 +122,116 @@ pub fn dump_stats<'a>(reason: impl Into<Option<&'a CStr>>) {
     }
 }
 
+#[doc(hidden)]
+#[cfg(feature = "valgrind")]
+#[macro_export]
+macro_rules! __dump_stats {
+    () => {
+        $crate::callgrind::dump_stats(None)
+    };
+    ($($arg:tt)+) => {{
+        let mut reason = $crate::callgrind::Reason::new();
+        let _ = ::core::fmt::Write::write_fmt(&mut reason, format_args!($($arg)+));
+        $crate::callgrind::dump_stats(reason.as_cstr());
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "valgrind"))]
+#[macro_export]
+macro_rules! __dump_stats {
+    () => {};
+    ($($arg:tt)+) => {{
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+#[doc = include_str!("../../doc/callgrind/dump_stats_macro.md")]
+pub use crate::__dump_stats as dump_stats;
+
+/// Maximum length of a formatted dump reason, in bytes; longer reasons are truncated.
+pub const REASON_LEN: usize = 255;
+
+// Stack buffer of `dump_stats!`.
+//
+// The buffer is zero-initialized and `write_str` skips nul bytes and never fills the last byte, so
+// the content is always a valid nul-terminated string.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Reason {
+    buf: [u8; REASON_LEN + 1],
+    len: usize,
+}
+
+impl Reason {
+    #[inline(always)]
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self { buf: [0; REASON_LEN + 1], len: 0 }
+    }
+
+    #[inline(always)]
+    pub fn as_cstr(&self) -> &CStr {
+        // SAFETY: see the type-level comment.
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.buf[..=self.len]) }
+    }
+}
+
+impl fmt::Write for Reason {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars().filter(|&c| c != '\0') {
+            let mut utf8 = [0; 4];
+            let bytes = c.encode_utf8(&mut utf8).as_bytes();
+            let end = self.len + bytes.len();
+
+            // truncated on a char boundary
+            if end > REASON_LEN {
+                return Err(fmt::Error);
+            }
+
+            self.buf[self.len..end].copy_from_slice(bytes);
+            self.len = end;
+        }
+        Ok(())
+    }
+}
+
+#[doc = include_str!("../../doc/callgrind/Dumper.md")]
+#[derive(Debug, Clone)]
+pub struct Dumper<'a> {
+    name: &'a str,
+    count: u64,
+}
+
+impl<'a> Dumper<'a> {
+    /// Creates a dumper naming the dumps `<name> #<n>`.
+    #[inline]
+    pub const fn new(name: &'a str) -> Self {
+        Self { name, count: 0 }
+    }
+
+    /// Dumps the costs since the previous dump as `<name> #<n>`, see [`dump_stats`](fn@dump_stats).
+    #[inline]
+    pub fn dump(&mut self) {
+        self.count += 1;
+        dump_stats!("{} #{}", self.name, self.count);
+    }
+
+    /// Dumps the costs since the previous dump as `<name> #<n>: <phase>`.
+    #[inline]
+    pub fn dump_phase(&mut self, phase: impl fmt::Display) {
+        self.count += 1;
+        dump_stats!("{} #{}: {phase}", self.name, self.count);
+    }
+
+    /// Number of dumps made so far.
+    #[inline]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+}
+
 #[doc = include_str!("../../doc/callgrind/zero_stats.md")]
 #[inline(always)]
 pub fn zero_stats() {
@@ -28,6 +244,64 @@ pub fn toggle_collect() {
     client_request!(CR::CG_CALLGRIND_TOGGLE_COLLECT);
 }
 
+#[doc = include_str!("../../doc/callgrind/collect.md")]
+#[cfg(feature = "std")]
+#[inline(always)]
+pub fn collect() -> ScopeGuard<Collecting> {
+    ScopeGuard::new(())
+}
+
+// Expansion of the `#[profile]` attribute, guards the function body.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __profile(reason: &'static CStr, zero: bool) -> ScopeGuard<Profiling> {
+    ScopeGuard::new((reason, zero))
+}
+
+// `&CStr` of a nul-terminated literal, checked at compile time in a `const` item.
+#[doc(hidden)]
+pub const fn __reason(bytes: &'static [u8]) -> &'static CStr {
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        assert!(bytes[i] != 0, "profile reason must not contain nul bytes");
+        i += 1;
+    }
+    assert!(
+        !bytes.is_empty() && bytes[bytes.len() - 1] == 0,
+        "profile reason must be nul-terminated"
+    );
+    // SAFETY: nul-terminated, without interior nul bytes
+    unsafe { CStr::from_bytes_with_nul_unchecked(bytes) }
+}
+
+#[doc(hidden)]
+#[cfg(feature = "valgrind")]
+#[macro_export]
+macro_rules! __profile {
+    ($reason:literal, $zero:literal, $body:block) => {{
+        const REASON: &::core::ffi::CStr =
+            $crate::callgrind::__reason(concat!($reason, "\0").as_bytes());
+        let _profile = $crate::callgrind::__profile(REASON, $zero);
+        $body
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "valgrind"))]
+#[macro_export]
+macro_rules! __profile {
+    ($reason:literal, $zero:literal, $body:block) => {
+        $body
+    };
+}
+
+#[doc = include_str!("../../doc/callgrind/instrument.md")]
+#[cfg(target_has_atomic = "ptr")]
+#[inline(always)]
+pub fn instrument() -> ScopeGuard<Instrumenting> {
+    ScopeGuard::new(())
+}
+
 #[doc = include_str!("../../doc/callgrind/start_instrumentation.md")]
 #[inline(always)]
 pub fn start_instrumentation() {
@@ -39,3 +313,9 @@ pub fn start_instrumentation() {
 pub fn stop_instrumentation() {
     client_request!(CR::CG_CALLGRIND_STOP_INSTRUMENTATION);
 }
+
+#[cfg(feature = "std")]
+impl Sealed for Collecting {}
+impl Sealed for Profiling {}
+#[cfg(target_has_atomic = "ptr")]
+impl Sealed for Instrumenting {}
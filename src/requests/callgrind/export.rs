@@ -0,0 +1,322 @@
+#![doc = include_str!("../../../doc/callgrind/export.md")]
+use super::format::{Call, Part, Profile};
+
+use core::fmt::Write;
+use std::{collections::HashMap, string::String, vec, vec::Vec};
+
+// Deeper stacks are cut, e.g. for a very deep recursion through long cycles.
+const MAX_DEPTH: usize = 256;
+// Calls costing less than this share of the part are folded into their caller. Shared callees
+// multiply the paths, e.g. `2^n` through `n` chained diamonds, and this bounds the walk to
+// `1 / MIN_SHARE` paths per depth.
+const MIN_SHARE: f64 = 1e-4;
+
+/// Exports the part as [folded stacks][folded], one `caller;callee <cost>` line per stack,
+/// the input of `flamegraph.pl` and [inferno].
+///
+/// Returns `None` if the part has no such `event`.
+///
+/// [folded]: https://github.com/brendangregg/FlameGraph#2-fold-stacks
+/// [inferno]: https://github.com/jonhoo/inferno
+pub fn folded(part: &Part, event: &str) -> Option<String> {
+    let mut out = String::new();
+
+    for (stack, cost) in stacks(part, part.event(event)?) {
+        for (i, &function) in stack.iter().enumerate() {
+            if i > 0 {
+                out.push(';');
+            }
+            // `;` separates the frames
+            let name = part.functions[function].name.chars();
+            out.extend(name.map(|c| if c == ';' { ',' } else { c }));
+        }
+        let _ = writeln!(out, " {cost}");
+    }
+
+    Some(out)
+}
+
+/// Exports the part as an uncompressed [pprof] protobuf `Profile` with a single sample type, the
+/// `event`.
+///
+/// `go tool pprof` reads both plain and gzip-compressed files.
+///
+/// Returns `None` if the part has no such `event`.
+///
+/// [pprof]: https://github.com/google/pprof/blob/main/proto/profile.proto
+pub fn pprof(part: &Part, event: &str) -> Option<Vec<u8>> {
+    let column = part.event(event)?;
+
+    let mut strings = Strings::default();
+    let mut profile = Proto::default();
+
+    // sample_type
+    let mut value_type = Proto::default();
+    value_type.uint(1, strings.id(event));
+    value_type.uint(2, strings.id("count"));
+    profile.message(1, &value_type);
+
+    // sample, leaf first
+    for (stack, cost) in stacks(part, column) {
+        let mut sample = Proto::default();
+        sample.packed(1, stack.iter().rev().map(|&function| function as u64 + 1));
+        sample.packed(2, [cost]);
+        profile.message(2, &sample);
+    }
+
+    // a location and a function per Callgrind function, sharing the ids
+    for (index, function) in part.functions.iter().enumerate() {
+        let id = index as u64 + 1;
+        let start_line = function.lines.keys().next().copied().unwrap_or(0);
+
+        let mut line = Proto::default();
+        line.uint(1, id);
+        line.uint(2, start_line);
+        let mut location = Proto::default();
+        location.uint(1, id);
+        location.message(4, &line);
+        profile.message(4, &location);
+
+        let mut func = Proto::default();
+        func.uint(1, id);
+        func.uint(2, strings.id(&function.name));
+        func.uint(3, strings.id(&function.name));
+        func.uint(4, strings.id(function.file.as_deref().unwrap_or_default()));
+        func.uint(5, start_line);
+        profile.message(5, &func);
+    }
+
+    // string_table
+    for string in &strings.table {
+        profile.bytes(6, string.as_bytes());
+    }
+
+    Some(profile.0)
+}
+
+#[doc = include_str!("../../../doc/callgrind/export/speedscope.md")]
+pub fn speedscope(profile: &Profile, event: &str) -> Option<String> {
+    let mut frames: Vec<(&str, &str)> = Vec::new();
+    let mut frame_ids: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut profiles = String::new();
+
+    for part in &profile.parts {
+        let Some(column) = part.event(event) else { continue };
+
+        let ids: Vec<usize> = part
+            .functions
+            .iter()
+            .map(|function| {
+                let key = (function.name.as_str(), function.file.as_deref().unwrap_or_default());
+                *frame_ids.entry(key).or_insert_with(|| {
+                    frames.push(key);
+                    frames.len() - 1
+                })
+            })
+            .collect();
+
+        let stacks = stacks(part, column);
+        let total: u64 = stacks.iter().map(|(_, cost)| cost).sum();
+
+        let sep = if profiles.is_empty() { "" } else { "," };
+        let _ = write!(
+            profiles,
+            r#"{sep}{{"type":"sampled","name":{},"unit":"none","startValue":0,"endValue":{total},"samples":["#,
+            Json(&std::format!("{} ({event})", part.name()))
+        );
+        for (i, (stack, _)) in stacks.iter().enumerate() {
+            let sep = if i == 0 { "[" } else { ",[" };
+            profiles.push_str(sep);
+            list(&mut profiles, stack.iter().map(|&function| ids[function]));
+            profiles.push(']');
+        }
+        profiles.push_str(r#"],"weights":["#);
+        list(&mut profiles, stacks.iter().map(|&(_, cost)| cost));
+        profiles.push_str("]}");
+    }
+
+    if profiles.is_empty() {
+        return None;
+    }
+
+    let mut out = String::from(
+        r#"{"$schema":"https://www.speedscope.app/file-format-schema.json","exporter":"crabgrind","#,
+    );
+    let name = profile.cmd.as_deref().unwrap_or("callgrind");
+    let _ = write!(out, r#""name":{},"activeProfileIndex":0,"shared":{{"frames":["#, Json(name));
+    for (i, (name, file)) in frames.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(out, r#"{sep}{{"name":{},"file":{}}}"#, Json(name), Json(file));
+    }
+    let _ = write!(out, r#"]}},"profiles":[{profiles}]}}"#);
+
+    Some(out)
+}
+
+// Call stacks(root first) with their exclusive costs of the `column` event.
+//
+// Callgrind keeps a call graph, not stacks, so the stacks are reconstructed by walking the graph
+// from the functions nobody calls, splitting the costs of a function between its callers in
+// proportion to the call costs. Calls closing a cycle are not followed, cheap calls are folded into
+// the caller(see `MIN_SHARE`).
+fn stacks(part: &Part, column: usize) -> Vec<(Vec<usize>, u64)> {
+    let mut callees: Vec<Vec<&Call>> = vec![Vec::new(); part.functions.len()];
+    let mut called = vec![false; part.functions.len()];
+    for call in part.calls.iter().filter(|call| call.caller != call.callee) {
+        callees[call.caller].push(call);
+        called[call.callee] = true;
+    }
+
+    let roots: Vec<usize> =
+        (0..part.functions.len()).filter(|&function| !called[function]).collect();
+    let total: f64 = roots.iter().map(|&root| cost(&part.functions[root].inclusive, column)).sum();
+    let min = (total * MIN_SHARE).max(0.5);
+
+    let mut walk = Walk { part, column, min, callees, path: Vec::new(), stacks: Vec::new() };
+    for root in roots {
+        walk.visit(root, cost(&part.functions[root].inclusive, column));
+    }
+
+    walk.stacks
+}
+
+struct Walk<'a> {
+    part: &'a Part,
+    column: usize,
+    // cheaper calls are folded into the caller
+    min: f64,
+    callees: Vec<Vec<&'a Call>>,
+    path: Vec<usize>,
+    stacks: Vec<(Vec<usize>, u64)>,
+}
+
+impl Walk<'_> {
+    // `value` is the inclusive cost of the function along the current path
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn visit(&mut self, function: usize, value: f64) {
+        if value < 0.5 || self.path.len() >= MAX_DEPTH || self.path.contains(&function) {
+            return;
+        }
+
+        let inclusive = cost(&self.part.functions[function].inclusive, self.column);
+        if inclusive == 0.0 {
+            return;
+        }
+        let share = value / inclusive;
+
+        self.path.push(function);
+
+        let folded: f64 = self.callees[function]
+            .iter()
+            .map(|call| cost(&call.inclusive, self.column) * share)
+            .filter(|&value| value < self.min)
+            .sum();
+        let exclusive =
+            cost(&self.part.functions[function].exclusive, self.column) * share + folded;
+        if exclusive >= 0.5 {
+            self.stacks.push((self.path.clone(), exclusive.round() as u64));
+        }
+        for i in 0..self.callees[function].len() {
+            let call = self.callees[function][i];
+            let value = cost(&call.inclusive, self.column) * share;
+            if value >= self.min {
+                self.visit(call.callee, value);
+            }
+        }
+
+        self.path.pop();
+    }
+}
+
+// Comma-separated values
+fn list(out: &mut String, values: impl Iterator<Item = impl core::fmt::Display>) {
+    for (i, value) in values.enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(out, "{sep}{value}");
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn cost(costs: &[u64], column: usize) -> f64 {
+    costs.get(column).copied().unwrap_or(0) as f64
+}
+
+// pprof string table, the empty string goes first
+struct Strings<'a> {
+    table: Vec<&'a str>,
+    ids: HashMap<&'a str, u64>,
+}
+
+impl Default for Strings<'_> {
+    fn default() -> Self {
+        Self { table: vec![""], ids: HashMap::from([("", 0)]) }
+    }
+}
+
+impl<'a> Strings<'a> {
+    fn id(&mut self, s: &'a str) -> u64 {
+        *self.ids.entry(s).or_insert_with(|| {
+            self.table.push(s);
+            self.table.len() as u64 - 1
+        })
+    }
+}
+
+// Protobuf encoder for the few wire types pprof uses.
+#[derive(Default)]
+struct Proto(Vec<u8>);
+
+impl Proto {
+    #[allow(clippy::cast_possible_truncation)]
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.0.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        self.0.push(n as u8);
+    }
+
+    fn uint(&mut self, field: u64, n: u64) {
+        if n != 0 {
+            self.varint(field << 3);
+            self.varint(n);
+        }
+    }
+
+    fn bytes(&mut self, field: u64, bytes: &[u8]) {
+        self.varint(field << 3 | 2);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn message(&mut self, field: u64, message: &Proto) {
+        self.bytes(field, &message.0);
+    }
+
+    fn packed(&mut self, field: u64, values: impl IntoIterator<Item = u64>) {
+        let mut packed = Proto::default();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes(field, &packed.0);
+    }
+}
+
+// JSON string literal
+struct Json<'a>(&'a str);
+
+impl core::fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
@@ -0,0 +1,490 @@
+#![doc = include_str!("../../../doc/callgrind/format.md")]
+use std::{
+    borrow::ToOwned,
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+    string::String,
+    vec::Vec,
+};
+
+/// Profile data of a single Callgrind output file, or of all the dumps of a run.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Profile {
+    /// Tool that wrote the profile (`creator:`).
+    pub creator: Option<String>,
+    /// Profiled process id (`pid:`).
+    pub pid: Option<u32>,
+    /// Profiled command line (`cmd:`).
+    pub cmd: Option<String>,
+    /// Profile parts, one per dump, ordered by the part number.
+    pub parts: Vec<Part>,
+}
+
+/// Profile data of a single dump.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Part {
+    /// Part number (`part:`), `0` if not specified.
+    pub number: u32,
+    /// Free-form descriptions (`desc:`), e.g. `Trigger: Client Request: <reason>`.
+    pub desc: Vec<String>,
+    /// Position columns of the cost lines (`positions:`), `line` by default.
+    pub positions: Vec<String>,
+    /// Event names (`events:`), the order of the cost columns.
+    pub events: Vec<String>,
+    /// Total costs from the header (`summary:`), empty if not specified.
+    pub summary: Vec<u64>,
+    /// Total costs from the footer (`totals:`), empty if not specified.
+    pub totals: Vec<u64>,
+    /// Functions with costs or calls.
+    pub functions: Vec<Function>,
+    /// Call edges, merged by the caller and callee.
+    pub calls: Vec<Call>,
+}
+
+/// Function cost center.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Function {
+    /// Function name, as written by Callgrind (demangled by default).
+    pub name: String,
+    /// ELF object (`ob=`).
+    pub object: Option<String>,
+    /// Source file (`fl=`).
+    pub file: Option<String>,
+    /// Costs of the function itself, per event.
+    pub exclusive: Vec<u64>,
+    /// Costs of the function and everything it called, per event.
+    ///
+    /// Recursive calls of a function to itself are not added, calls within longer cycles are.
+    pub inclusive: Vec<u64>,
+    /// Exclusive costs per source line, if `line` is one of the positions.
+    pub lines: BTreeMap<u64, Vec<u64>>,
+}
+
+/// Call edge between two functions.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Call {
+    /// Index of the calling function in [`Part::functions`].
+    pub caller: usize,
+    /// Index of the called function in [`Part::functions`].
+    pub callee: usize,
+    /// Number of calls.
+    pub count: u64,
+    /// Inclusive costs of the calls, per event.
+    pub inclusive: Vec<u64>,
+}
+
+/// Profile parsing errors.
+#[derive(Debug)]
+pub enum FormatError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// Malformed line.
+    Syntax {
+        /// Line number, starting at 1.
+        line: usize,
+        /// What's wrong with it.
+        reason: &'static str,
+    },
+}
+
+impl Profile {
+    /// Parses the profile data file contents.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Syntax`] - The text is not a valid profile.
+    pub fn parse(text: &str) -> Result<Self, FormatError> {
+        Parser::default().parse(text)
+    }
+
+    /// Reads and parses the profile data file.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Io`] - The file could not be read.
+    /// - [`FormatError::Syntax`] - The file is not a valid profile.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Reads and merges all the dumps of a run: the `path` itself(e.g. `callgrind.out.<pid>`), if
+    /// exists, and the `path.<n>` parts. With `--separate-threads=yes`, the `path-<tid>` and
+    /// `path.<n>-<tid>` files.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Io`] - None of the files exist, or some could not be read.
+    /// - [`FormatError::Syntax`] - Some of the files are not valid profiles.
+    pub fn read_dumps(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        let path = path.as_ref();
+        let base = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+        let mut profile = Self::default();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(suffix) = name.to_str().and_then(|name| name.strip_prefix(base)) else {
+                continue;
+            };
+            if !is_dump_suffix(suffix) {
+                continue;
+            }
+
+            let part = Self::read(entry.path())?;
+            profile.creator = profile.creator.or(part.creator);
+            profile.pid = profile.pid.or(part.pid);
+            profile.cmd = profile.cmd.or(part.cmd);
+            profile.parts.extend(part.parts);
+        }
+
+        if profile.parts.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::NotFound).into());
+        }
+        profile.parts.sort_by_key(|part| part.number);
+
+        Ok(profile)
+    }
+
+    /// The part dumped with the `reason`, see [`dump_stats`](super::dump_stats).
+    pub fn dump(&self, reason: &str) -> Option<&Part> {
+        self.parts.iter().find(|part| part.trigger() == Some(reason))
+    }
+}
+
+// `""`, `.<n>`, `-<tid>` or `.<n>-<tid>`, the suffixes of the files written by a run.
+fn is_dump_suffix(suffix: &str) -> bool {
+    let number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (dump, thread) = match suffix.split_once('-') {
+        Some((dump, thread)) => (dump, Some(thread)),
+        None => (suffix, None),
+    };
+
+    (dump.is_empty() || dump.strip_prefix('.').is_some_and(number)) && thread.map_or(true, number)
+}
+
+impl Part {
+    /// Column of the event in the cost vectors.
+    pub fn event(&self, name: &str) -> Option<usize> {
+        self.events.iter().position(|event| event == name)
+    }
+
+    /// Dump reason given to [`dump_stats`](super::dump_stats), if the part was dumped by a
+    /// client request.
+    pub fn trigger(&self) -> Option<&str> {
+        self.desc.iter().find_map(|desc| desc.strip_prefix("Trigger: Client Request: "))
+    }
+
+    /// Display name of the part: the dump reason, the trigger (e.g. `Program termination`), or
+    /// `part <number>`.
+    pub fn name(&self) -> String {
+        let trigger = self.desc.iter().find_map(|desc| desc.strip_prefix("Trigger: "));
+        match (self.trigger(), trigger) {
+            (Some(reason), _) | (None, Some(reason)) => reason.into(),
+            (None, None) => std::format!("part {}", self.number),
+        }
+    }
+
+    /// First function with the `name`.
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Calls made by the function at `index`.
+    pub fn callees(&self, index: usize) -> impl Iterator<Item = &Call> {
+        self.calls.iter().filter(move |call| call.caller == index)
+    }
+
+    /// Calls of the function at `index`.
+    pub fn callers(&self, index: usize) -> impl Iterator<Item = &Call> {
+        self.calls.iter().filter(move |call| call.callee == index)
+    }
+
+    // Inclusive costs, once all the calls are known.
+    fn finish(&mut self) {
+        for function in &mut self.functions {
+            function.inclusive.clone_from(&function.exclusive);
+        }
+        for call in self.calls.iter().filter(|call| call.caller != call.callee) {
+            add(&mut self.functions[call.caller].inclusive, &call.inclusive);
+        }
+    }
+}
+
+// (object, file, name)
+type FunctionKey = (Option<String>, Option<String>, String);
+
+#[derive(Default)]
+struct Parser {
+    profile: Profile,
+    part: Part,
+    // name compression tables, shared by all the parts of a file
+    objects: HashMap<u64, String>,
+    files: HashMap<u64, String>,
+    names: HashMap<u64, String>,
+    // per-part lookups
+    keys: HashMap<FunctionKey, usize>,
+    edges: HashMap<(usize, usize), usize>,
+    // current context
+    object: Option<String>,
+    file: Option<String>,
+    function: Option<usize>,
+    // target of the next call
+    call_object: Option<String>,
+    call_file: Option<String>,
+    call_name: Option<String>,
+    // callee index and count, the next cost line is the call cost
+    call: Option<(usize, u64)>,
+    // the next line is the source position of a jump
+    jump: bool,
+    // costs are in an inlined file, other than the function's one
+    inlined: bool,
+    last_position: Vec<u64>,
+}
+
+impl Parser {
+    fn parse(mut self, text: &str) -> Result<Profile, FormatError> {
+        for (i, line) in text.lines().enumerate() {
+            self.line(line.trim_end())
+                .map_err(|reason| FormatError::Syntax { line: i + 1, reason })?;
+        }
+
+        self.finish_part();
+        Ok(self.profile)
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), &'static str> {
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+
+        let first = line.as_bytes()[0];
+        if first.is_ascii_digit() || matches!(first, b'+' | b'-' | b'*') {
+            if core::mem::take(&mut self.jump) {
+                return self.position(&mut line.split_whitespace()).map(drop);
+            }
+            return self.costs(line);
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            if key.bytes().all(|b| b.is_ascii_alphabetic()) {
+                return self.spec(key, value);
+            }
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            return self.header(key, value.trim());
+        }
+
+        Err("unrecognized line")
+    }
+
+    fn header(&mut self, key: &str, value: &str) -> Result<(), &'static str> {
+        let costs = |value: &str| value.split_whitespace().map(number).collect::<Option<Vec<_>>>();
+
+        match key {
+            "creator" => self.profile.creator = Some(value.into()),
+            "pid" => self.profile.pid = Some(value.parse().map_err(|_| "invalid pid")?),
+            "cmd" => self.profile.cmd = Some(value.into()),
+            "part" => {
+                if !self.part.functions.is_empty() || !self.part.totals.is_empty() {
+                    self.finish_part();
+                }
+                self.part.number = value.parse().map_err(|_| "invalid part number")?;
+            }
+            "desc" => self.part.desc.push(value.into()),
+            "positions" => {
+                self.part.positions = value.split_whitespace().map(ToOwned::to_owned).collect();
+            }
+            "events" => {
+                self.part.events = value.split_whitespace().map(ToOwned::to_owned).collect();
+            }
+            "summary" => self.part.summary = costs(value).ok_or("invalid summary")?,
+            "totals" => self.part.totals = costs(value).ok_or("invalid totals")?,
+            // version, event descriptions, and the like
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn spec(&mut self, key: &str, value: &str) -> Result<(), &'static str> {
+        match key {
+            "ob" => self.object = Some(compressed(&mut self.objects, value)?),
+            "fl" => {
+                self.file = Some(compressed(&mut self.files, value)?);
+                self.inlined = false;
+            }
+            // inlined code, doesn't change the function
+            "fi" | "fe" => {
+                let file = compressed(&mut self.files, value)?;
+                self.inlined = self.file.as_ref() != Some(&file);
+            }
+            "fn" => {
+                self.inlined = false;
+                let name = compressed(&mut self.names, value)?;
+                let key = (self.object.clone(), self.file.clone(), name);
+                self.function = Some(self.function_index(key));
+            }
+            "cob" => self.call_object = Some(compressed(&mut self.objects, value)?),
+            "cfi" | "cfl" => self.call_file = Some(compressed(&mut self.files, value)?),
+            "cfn" => self.call_name = Some(compressed(&mut self.names, value)?),
+            "calls" => {
+                let count =
+                    value.split_whitespace().next().and_then(number).ok_or("invalid calls")?;
+                let name = self.call_name.take().ok_or("`calls=` without `cfn=`")?;
+                let key = (
+                    self.call_object.take().or_else(|| self.object.clone()),
+                    self.call_file.take().or_else(|| self.file.clone()),
+                    name,
+                );
+                self.call = Some((self.function_index(key), count));
+            }
+            "jump" | "jcnd" => self.jump = true,
+            _ => return Err("unknown specification"),
+        }
+
+        Ok(())
+    }
+
+    // Parses the position columns, returns the source line if it's one of them.
+    fn position<'a>(
+        &mut self,
+        tokens: &mut impl Iterator<Item = &'a str>,
+    ) -> Result<Option<u64>, &'static str> {
+        let columns = self.part.positions.len().max(1);
+        let mut line_number = None;
+
+        self.last_position.resize(columns, 0);
+        for column in 0..columns {
+            let token = tokens.next().ok_or("missing position")?;
+            let last = self.last_position[column];
+
+            let position = match token.as_bytes()[0] {
+                b'*' => Some(last),
+                b'+' => number(&token[1..]).and_then(|n| last.checked_add(n)),
+                b'-' => number(&token[1..]).and_then(|n| last.checked_sub(n)),
+                _ => number(token),
+            }
+            .ok_or("invalid position")?;
+
+            self.last_position[column] = position;
+            if self.part.positions.get(column).map_or(columns == 1, |name| name == "line") {
+                line_number = Some(position);
+            }
+        }
+
+        Ok(line_number)
+    }
+
+    fn costs(&mut self, line: &str) -> Result<(), &'static str> {
+        let mut tokens = line.split_whitespace();
+        let line_number = self.position(&mut tokens)?.filter(|_| !self.inlined);
+
+        let mut costs: Vec<u64> =
+            tokens.map(number).collect::<Option<_>>().ok_or("invalid cost")?;
+        costs.resize(costs.len().max(self.part.events.len()), 0);
+
+        let caller = self.function.ok_or("costs outside of a function")?;
+        if let Some((callee, count)) = self.call.take() {
+            let edge = *self.edges.entry((caller, callee)).or_insert_with(|| {
+                self.part.calls.push(Call { caller, callee, ..Call::default() });
+                self.part.calls.len() - 1
+            });
+
+            let call = &mut self.part.calls[edge];
+            call.count = call.count.saturating_add(count);
+            add(&mut call.inclusive, &costs);
+        } else {
+            let function = &mut self.part.functions[caller];
+            add(&mut function.exclusive, &costs);
+            if let Some(line) = line_number {
+                add(function.lines.entry(line).or_default(), &costs);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn function_index(&mut self, key: FunctionKey) -> usize {
+        if let Some(&index) = self.keys.get(&key) {
+            return index;
+        }
+
+        let (object, file, name) = key.clone();
+        self.part.functions.push(Function { name, object, file, ..Function::default() });
+        self.keys.insert(key, self.part.functions.len() - 1);
+        self.part.functions.len() - 1
+    }
+
+    fn finish_part(&mut self) {
+        let next = Part {
+            positions: self.part.positions.clone(),
+            events: self.part.events.clone(),
+            ..Part::default()
+        };
+        let mut part = core::mem::replace(&mut self.part, next);
+
+        self.keys.clear();
+        self.edges.clear();
+        self.function = None;
+
+        if !part.functions.is_empty() || !part.totals.is_empty() || !part.summary.is_empty() {
+            part.finish();
+            self.profile.parts.push(part);
+        }
+    }
+}
+
+// `(id) name`, `(id)` or `name`
+fn compressed(table: &mut HashMap<u64, String>, spec: &str) -> Result<String, &'static str> {
+    let Some(rest) = spec.strip_prefix('(') else {
+        return Ok(spec.into());
+    };
+
+    let (id, name) = rest.split_once(')').ok_or("unterminated name id")?;
+    let id = number(id).ok_or("invalid name id")?;
+    let name = name.trim_start();
+
+    if name.is_empty() {
+        table.get(&id).cloned().ok_or("undefined name id")
+    } else {
+        table.insert(id, name.into());
+        Ok(name.into())
+    }
+}
+
+// Decimal or `0x` prefixed hexadecimal.
+fn number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn add(acc: &mut Vec<u64>, costs: &[u64]) {
+    if acc.len() < costs.len() {
+        acc.resize(costs.len(), 0);
+    }
+    for (acc, cost) in acc.iter_mut().zip(costs) {
+        *acc = acc.saturating_add(*cost);
+    }
+}
+
+impl From<io::Error> for FormatError {
+    #[inline(always)]
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl core::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Syntax { line, reason } => write!(f, "line {line}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
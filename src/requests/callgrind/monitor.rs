@@ -0,0 +1,97 @@
+#![doc = include_str!("../../../doc/callgrind/monitor.md")]
+use crate::requests::command::tool_commands;
+pub use crate::valgrind::monitor::Command;
+
+use core::fmt::{self, Display, Formatter};
+
+/// `dump` command builder.
+///
+/// The monitor command counterpart of [`dump_stats`](super::dump_stats).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct DumpCmd<'a> {
+    hint: Option<&'a str>,
+}
+
+impl<'a> DumpCmd<'a> {
+    /// Dumps the profile data, with an optional `hint` written into the dump header
+    /// (`dump [<dump_hint>]`).
+    #[inline(always)]
+    pub fn new(hint: impl Into<Option<&'a str>>) -> Self {
+        Self { hint: hint.into() }
+    }
+}
+
+/// `zero` command builder.
+///
+/// The monitor command counterpart of [`zero_stats`](super::zero_stats).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ZeroCmd;
+
+/// `status` command builder.
+///
+/// Shows the Callgrind status, e.g. the number of dumps made and the current instrumentation
+/// state.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct StatusCmd;
+
+/// `instrumentation` command builder.
+///
+/// The monitor command counterpart of [`start_instrumentation`](super::start_instrumentation) and
+/// [`stop_instrumentation`](super::stop_instrumentation).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct InstrumentationCmd {
+    state: Option<bool>,
+}
+
+impl InstrumentationCmd {
+    /// Shows the current instrumentation state(`instrumentation`).
+    #[inline(always)]
+    pub const fn query() -> Self {
+        Self { state: None }
+    }
+
+    /// Switches the instrumentation on(`instrumentation on`).
+    #[inline(always)]
+    pub const fn on() -> Self {
+        Self { state: Some(true) }
+    }
+
+    /// Switches the instrumentation off(`instrumentation off`).
+    #[inline(always)]
+    pub const fn off() -> Self {
+        Self { state: Some(false) }
+    }
+}
+
+impl Display for DumpCmd<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.hint {
+            Some(hint) => write!(f, "dump {hint}"),
+            None => f.write_str("dump"),
+        }
+    }
+}
+
+impl Display for ZeroCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("zero")
+    }
+}
+
+impl Display for StatusCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("status")
+    }
+}
+
+impl Display for InstrumentationCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.state {
+            Some(true) => f.write_str("instrumentation on"),
+            Some(false) => f.write_str("instrumentation off"),
+            None => f.write_str("instrumentation"),
+        }
+    }
+}
+
+tool_commands!("callgrind": DumpCmd<'_>, ZeroCmd, StatusCmd, InstrumentationCmd);
@@ -0,0 +1,57 @@
+use super::{Reason, dump_stats, zero_stats};
+use crate::valgrind::running_mode;
+
+use core::{
+    ffi::c_int,
+    fmt::Write,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::io;
+
+// Number of the signal-triggered dumps
+static DUMPS: AtomicUsize = AtomicUsize::new(0);
+
+#[doc = include_str!("../../../doc/callgrind/install_signal_handlers.md")]
+pub fn install_signal_handlers() -> io::Result<()> {
+    if !cfg!(feature = "valgrind") || running_mode().is_native() {
+        return Ok(());
+    }
+
+    let handlers: [(c_int, extern "C" fn(c_int)); 2] =
+        [(libc::SIGUSR1, on_dump), (libc::SIGUSR2, on_zero)];
+
+    for (signal, handler) in handlers {
+        // SAFETY: the handlers only issue client requests and format into a stack buffer, which
+        // is async-signal-safe.
+        unsafe {
+            let mut action: libc::sigaction = core::mem::zeroed();
+            action.sa_sigaction = handler as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            if libc::sigaction(signal, &action, ptr::null_mut()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// `SIGUSR1 #<n> at <seconds>.<millis>s`, the time is `CLOCK_MONOTONIC`
+extern "C" fn on_dump(_: c_int) {
+    let n = DUMPS.fetch_add(1, Ordering::Relaxed) + 1;
+
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `clock_gettime` is async-signal-safe, `now` is a valid `timespec`.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+
+    let mut reason = Reason::new();
+    let _ = write!(reason, "SIGUSR1 #{n} at {}.{:03}s", now.tv_sec, now.tv_nsec / 1_000_000);
+    dump_stats(reason.as_cstr());
+}
+
+extern "C" fn on_zero(_: c_int) {
+    zero_stats();
+}
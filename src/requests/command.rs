@@ -0,0 +1,95 @@
+use super::valgrind::{MONITOR_COMMAND_MAX, MonitorError, monitor_command, toolname};
+use core::{
+    ffi::CStr,
+    fmt::{self, Write},
+};
+
+// Stack buffer for the monitor commands built by typed wrappers.
+//
+// The buffer is zero-initialized and `write_str` never writes nul bytes nor fills the last byte,
+// so the content is always a valid nul-terminated string.
+pub(crate) struct CommandBuf {
+    buf: [u8; MONITOR_COMMAND_MAX],
+    len: usize,
+}
+
+impl CommandBuf {
+    #[inline(always)]
+    pub(crate) const fn new() -> Self {
+        Self { buf: [0; MONITOR_COMMAND_MAX], len: 0 }
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_cstr(&self) -> &CStr {
+        // SAFETY: see the type-level comment.
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.buf[..=self.len]) }
+    }
+}
+
+impl Write for CommandBuf {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let bytes = s.as_bytes();
+        let end = self.len + bytes.len();
+
+        if end >= MONITOR_COMMAND_MAX || bytes.contains(&0) {
+            return Err(fmt::Error);
+        }
+
+        self.buf[self.len..end].copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+// Formats `cmd` into a stack buffer and executes it with `VALGRIND_MONITOR_COMMAND`.
+#[inline]
+pub(crate) fn send(cmd: fmt::Arguments<'_>) -> Result<(), MonitorError> {
+    let mut buf = CommandBuf::new();
+    buf.write_fmt(cmd).map_err(|_| MonitorError::InvalidCommand)?;
+
+    monitor_command(buf.as_cstr()).map_err(|()| MonitorError::NotFound)
+}
+
+// Verifies that `tool` is the running one, before sending it a tool-specific command.
+//
+// The tool name is known only with `VALGRIND_GET_TOOLNAME` (Valgrind 3.27), otherwise the check is
+// skipped and an unknown command is reported by Valgrind itself with `MONITOR_COMMAND_ERROR`.
+#[inline]
+pub(crate) fn check_tool(tool: &str) -> Result<(), MonitorError> {
+    if crate::VALGRIND_VERSION < (3, 27) {
+        return Ok(());
+    }
+
+    let mut buf = [0u8; 64];
+    match toolname(&mut buf) {
+        Some(name) if !name.to_bytes().eq_ignore_ascii_case(tool.as_bytes()) => {
+            Err(MonitorError::WrongTool)
+        }
+        _ => Ok(()),
+    }
+}
+
+// Monitor commands are tokenized on whitespace, so single-token arguments(e.g. file names) may not
+// contain any.
+#[inline]
+pub(crate) fn token(arg: &str) -> Result<&str, MonitorError> {
+    if arg.is_empty() || arg.contains(char::is_whitespace) {
+        Err(MonitorError::InvalidCommand)
+    } else {
+        Ok(arg)
+    }
+}
+
+// Implements `Command` for the commands understood only by `$tool`.
+macro_rules! tool_commands {
+    ($tool:literal: $($cmd:ty),+ $(,)?) => {
+        $(
+            impl $crate::valgrind::monitor::Command for $cmd {
+                const TOOL: Option<&'static str> = Some($tool);
+            }
+        )+
+    };
+}
+
+pub(crate) use tool_commands;
@@ -2,6 +2,9 @@ pub mod dhat {
     // <valgrind/dhat.h>:
     // "... If no meaningful weight argument exists, just use 1."
     pub const AD_HOC_EVENT_DEFAULT_WEIGHT: usize = 1;
+    // dh_main.c: histograms requested with DHAT_HISTOGRAM_MEMORY are limited to 25 KiB, instead of
+    // the default 1 KiB.
+    pub const HISTOGRAM_MAX_SIZE: usize = 25_600;
 }
 
 pub mod memcheck {
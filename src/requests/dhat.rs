@@ -1,19 +1,96 @@
 #![doc = include_str!("../../doc/dhat.md")]
-use super::{client_request, constants::dhat::AD_HOC_EVENT_DEFAULT_WEIGHT};
+use super::{
+    client_request,
+    constants::dhat::{AD_HOC_EVENT_DEFAULT_WEIGHT, HISTOGRAM_MAX_SIZE as MAX_SIZE},
+};
 
 #[cfg(feature = "valgrind")]
 use crate::bindings::CG_DHATClientRequest as CR;
 
 use core::ffi::c_void;
 
+#[cfg(feature = "std")]
+mod budget;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+mod json;
+
+#[cfg(feature = "std")]
+pub use budget::{HEAP_BUDGET_ENV, assert_heap_budget, assert_test_heap_budget};
+
+use alloc::boxed::Box;
+
+/// Largest block size for which [`histogram_memory`] enables the access histogram.
+pub const HISTOGRAM_MAX_SIZE: usize = MAX_SIZE;
+
+#[doc = include_str!("../../doc/dhat/HistogramError.md")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum HistogramError {
+    #[doc = include_str!("../../doc/dhat/histogramerror/zero_sized.md")]
+    ZeroSized,
+    #[doc = include_str!("../../doc/dhat/histogramerror/too_large.md")]
+    TooLarge(usize),
+}
+
 #[doc = include_str!("../../doc/dhat/ad_hoc_event.md")]
 #[inline(always)]
 pub fn ad_hoc_event(weight: impl Into<Option<usize>>) {
     client_request!(CR::CG_DHAT_AD_HOC_EVENT, weight.into().unwrap_or(AD_HOC_EVENT_DEFAULT_WEIGHT));
 }
 
+#[doc = include_str!("../../doc/dhat/event.md")]
+pub use crabgrind_macros::event;
+
 #[doc = include_str!("../../doc/dhat/histogram_memory.md")]
 #[inline(always)]
 pub fn histogram_memory(addr: *const c_void) {
     client_request!(CR::CG_DHAT_HISTOGRAM_MEMORY, addr);
 }
+
+#[doc = include_str!("../../doc/dhat/histogram.md")]
+#[inline]
+#[allow(clippy::borrowed_box)]
+pub fn histogram<T>(block: &Box<T>) -> Result<(), HistogramError> {
+    histogram_block(core::ptr::addr_of!(**block).cast(), core::mem::size_of::<T>())
+}
+
+#[doc = include_str!("../../doc/dhat/histogram_slice.md")]
+#[inline]
+#[allow(clippy::borrowed_box)]
+pub fn histogram_slice<T>(block: &Box<[T]>) -> Result<(), HistogramError> {
+    histogram_block(block.as_ptr().cast(), core::mem::size_of_val::<[T]>(block))
+}
+
+// A non-zero-sized `Box` owns a block allocated by the global allocator, starting at its pointer.
+#[inline(always)]
+fn histogram_block(addr: *const c_void, size: usize) -> Result<(), HistogramError> {
+    match size {
+        0 => Err(HistogramError::ZeroSized),
+        size if size > HISTOGRAM_MAX_SIZE => Err(HistogramError::TooLarge(size)),
+        _ => {
+            histogram_memory(addr);
+            Ok(())
+        }
+    }
+}
+
+impl core::fmt::Display for HistogramError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ZeroSized => write!(f, "zero-sized values have no heap block"),
+            Self::TooLarge(size) => {
+                write!(
+                    f,
+                    "{size} bytes block exceeds the {HISTOGRAM_MAX_SIZE} bytes histogram limit"
+                )
+            }
+        }
+    }
+}
+
+// `has_core_error` is set by build.rs
+#[cfg(has_core_error)]
+impl core::error::Error for HistogramError {}
+#[cfg(all(feature = "std", not(has_core_error)))]
+impl std::error::Error for HistogramError {}
@@ -0,0 +1,132 @@
+use super::format::Profile;
+use crate::valgrind::{ToolConfig, running_mode, temp_file};
+
+use std::{
+    cell::Cell,
+    env, format, fs,
+    io::{self, Write},
+    process,
+    string::{String, ToString},
+};
+
+/// Environment variable set for the process started by [`assert_heap_budget`], naming the region
+/// to measure.
+pub const HEAP_BUDGET_ENV: &str = "CRABGRIND_HEAP_BUDGET";
+
+// Name of the function the measured region runs in, allocations are attributed by their stacks.
+const REGION: &str = "__crabgrind_heap_region";
+
+// Written to `stderr` by the re-executed process once the region ran, e.g. not filtered out.
+const MEASURED: &str = "crabgrind: heap region measured";
+
+std::thread_local! {
+    // Regions of the current thread, a test runs on its own thread
+    static REGIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Allocations of the region
+#[derive(Debug, Default, Clone, Copy)]
+struct HeapUsage {
+    total_bytes: u64,
+    total_blocks: u64,
+    // Sums of the per-site maximums, an upper bound of the region peak
+    peak_bytes: u64,
+    peak_blocks: u64,
+}
+
+#[doc = include_str!("../../../doc/dhat/assert_heap_budget.md")]
+#[track_caller]
+pub fn assert_heap_budget<R>(max_bytes: u64, max_blocks: u64, f: impl FnOnce() -> R) -> R {
+    heap_budget(None, max_bytes, max_blocks, f)
+}
+
+#[doc = include_str!("../../../doc/dhat/assert_test_heap_budget.md")]
+#[track_caller]
+pub fn assert_test_heap_budget<R>(
+    test: &str,
+    max_bytes: u64,
+    max_blocks: u64,
+    f: impl FnOnce() -> R,
+) -> R {
+    heap_budget(Some(test), max_bytes, max_blocks, f)
+}
+
+#[track_caller]
+fn heap_budget<R>(test: Option<&str>, max_bytes: u64, max_blocks: u64, f: impl FnOnce() -> R) -> R {
+    // the re-executed process runs only the current test, with the same regions before this one
+    let id = REGIONS.with(|regions| regions.replace(regions.get() + 1)).to_string();
+
+    match env::var(HEAP_BUDGET_ENV) {
+        // re-executed process, run the region and exit
+        Ok(region) if region == id => {
+            __crabgrind_heap_region(f);
+            let _ = writeln!(io::stderr(), "{MEASURED}");
+            process::exit(0);
+        }
+        Ok(_) => return f(),
+        Err(_) if !cfg!(feature = "valgrind") || !running_mode().is_native() => return f(),
+        Err(_) => {}
+    }
+
+    let usage = match measure(&id, test) {
+        Ok(usage) => usage,
+        Err(err) => panic!("heap budget: {err}"),
+    };
+    assert!(
+        usage.total_bytes <= max_bytes && usage.total_blocks <= max_blocks,
+        "heap budget of {max_bytes} bytes in {max_blocks} blocks exceeded: {} bytes in {} blocks \
+         (peak at most {} bytes in {} blocks)",
+        usage.total_bytes,
+        usage.total_blocks,
+        usage.peak_bytes,
+        usage.peak_blocks,
+    );
+
+    f()
+}
+
+#[inline(never)]
+fn __crabgrind_heap_region<R>(f: impl FnOnce() -> R) -> R {
+    // the call is not a tail call, so the frame stays on the stack
+    core::hint::black_box(f())
+}
+
+// Runs the region under DHAT, in a copy of the process.
+fn measure(id: &str, test: Option<&str>) -> Result<HeapUsage, String> {
+    let out_file = temp_file(&format!("crabgrind-heap.{id}")).map_err(|err| err.to_string())?;
+
+    let mut config = ToolConfig::new("dhat").flags([
+        format!("--dhat-out-file={}", out_file.display()),
+        // the region frame may be deep in the allocation stacks
+        "--num-callers=500".into(),
+    ]);
+    // only the current test, if it's a test
+    if let Some(test) = test {
+        config = config.args([test, "--exact", "--test-threads=1"]);
+    }
+
+    let output = config
+        .command()
+        .and_then(|mut cmd| cmd.env(HEAP_BUDGET_ENV, id).output())
+        .map_err(|err| err.to_string())?;
+    let profile = Profile::read(&out_file);
+    let _ = fs::remove_file(&out_file);
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.contains(MEASURED) {
+        return Err(stderr.into_owned());
+    }
+    let profile = profile.map_err(|err| err.to_string())?;
+
+    let mut usage = HeapUsage::default();
+    for point in &profile.points {
+        if profile.stack(point).any(|frame| frame.contains(REGION)) {
+            usage.total_bytes += point.total_bytes;
+            usage.total_blocks += point.total_blocks;
+            usage.peak_bytes += point.max_bytes.unwrap_or(point.total_bytes);
+            usage.peak_blocks += point.max_blocks.unwrap_or(point.total_blocks);
+        }
+    }
+
+    Ok(usage)
+}
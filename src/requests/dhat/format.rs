@@ -0,0 +1,353 @@
+#![doc = include_str!("../../../doc/dhat/format.md")]
+use super::json::{self, Value};
+
+use core::ops::Range;
+use std::{collections::BTreeMap, fs, io, path::Path, string::String, vec::Vec};
+
+// Prefix of the functions generated by `dhat::event!`, followed by the label.
+const EVENT_PREFIX: &str = "__crabgrind_event_";
+
+/// DHAT profiling mode (`--mode`).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Mode {
+    /// Heap allocations, the default.
+    #[default]
+    Heap,
+    /// Memory copies, e.g. `memcpy` and `strcpy`.
+    Copy,
+    /// Ad-hoc events, see [`ad_hoc_event`](super::ad_hoc_event).
+    AdHoc,
+}
+
+/// Profile data of a DHAT output file.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Profile {
+    /// File format version (`dhatFileVersion`).
+    pub version: u64,
+    /// Profiling mode.
+    pub mode: Mode,
+    /// Profiled command line.
+    pub cmd: String,
+    /// Profiled process id.
+    pub pid: u64,
+    /// Verb of the counted operations, e.g. `Allocated`.
+    pub verb: String,
+    /// Time unit, e.g. `instrs`.
+    pub time_unit: String,
+    /// Time at the end of the run, in [`time_unit`](Profile::time_unit)s.
+    pub time_end: u64,
+    /// Time of the global heap maximum, [`Mode::Heap`] only.
+    pub time_global_max: Option<u64>,
+    /// Whether block lifetimes (`tl`, `mb`, `gb`, `fb`) were recorded.
+    pub block_lifetimes: bool,
+    /// Whether block accesses (`rb`, `wb`, `acc`) were recorded.
+    pub block_accesses: bool,
+    /// Program points, one per allocation stack.
+    pub points: Vec<ProgramPoint>,
+    /// Frame table, indexed by [`ProgramPoint::frames`]. The first frame is `[root]`.
+    pub frames: Vec<String>,
+}
+
+/// Costs of a program point, the allocation sites sharing a stack.
+///
+/// The lifetime and access fields are `None` when not recorded, see [`Profile::block_lifetimes`]
+/// and [`Profile::block_accesses`].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ProgramPoint {
+    /// Total bytes (or ad-hoc units).
+    pub total_bytes: u64,
+    /// Total blocks (or ad-hoc events).
+    pub total_blocks: u64,
+    /// Sum of the block lifetimes.
+    pub total_lifetimes: Option<u64>,
+    /// Maximum live bytes.
+    pub max_bytes: Option<u64>,
+    /// Maximum live blocks.
+    pub max_blocks: Option<u64>,
+    /// Live bytes at the global heap maximum.
+    pub global_max_bytes: Option<u64>,
+    /// Live blocks at the global heap maximum.
+    pub global_max_blocks: Option<u64>,
+    /// Live bytes at the end of the run.
+    pub end_bytes: Option<u64>,
+    /// Live blocks at the end of the run.
+    pub end_blocks: Option<u64>,
+    /// Bytes read from the blocks.
+    pub reads: Option<u64>,
+    /// Bytes written to the blocks.
+    pub writes: Option<u64>,
+    /// Access counts per byte offset, when all the blocks have the same small size.
+    pub accesses: Option<Vec<u64>>,
+    /// Stack, innermost frame first, as indices into [`Profile::frames`].
+    pub frames: Vec<usize>,
+}
+
+/// Totals of the ad-hoc events recorded with a label, see [`event!`](super::event).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct EventTotals {
+    /// Sum of the event weights.
+    pub units: u64,
+    /// Number of events.
+    pub events: u64,
+}
+
+/// Profile parsing errors.
+#[derive(Debug)]
+pub enum FormatError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// Malformed JSON.
+    Syntax {
+        /// Line number, starting at 1.
+        line: usize,
+        /// What's wrong with it.
+        reason: &'static str,
+    },
+    /// Well-formed JSON, but not a DHAT profile.
+    InvalidField(&'static str),
+}
+
+impl Profile {
+    /// Parses the profile data file contents.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Syntax`] - The text is not valid JSON.
+    /// - [`FormatError::InvalidField`] - A field is missing or has the wrong type.
+    pub fn parse(text: &str) -> Result<Self, FormatError> {
+        let root = json::parse(text).map_err(|(offset, reason)| FormatError::Syntax {
+            line: text[..offset].matches('\n').count() + 1,
+            reason,
+        })?;
+
+        let mode = match string(&root, "mode")? {
+            "heap" => Mode::Heap,
+            "copy" => Mode::Copy,
+            "ad-hoc" => Mode::AdHoc,
+            _ => return Err(FormatError::InvalidField("mode")),
+        };
+        let frames = array(&root, "ftbl")?
+            .iter()
+            .map(|frame| frame.as_str().map(String::from))
+            .collect::<Option<_>>()
+            .ok_or(FormatError::InvalidField("ftbl"))?;
+        let points =
+            array(&root, "pps")?.iter().map(ProgramPoint::parse).collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            version: number(&root, "dhatFileVersion")?,
+            mode,
+            cmd: string(&root, "cmd")?.into(),
+            pid: number(&root, "pid")?,
+            verb: string(&root, "verb")?.into(),
+            time_unit: string(&root, "tu")?.into(),
+            time_end: number(&root, "te")?,
+            time_global_max: optional(&root, "tg")?,
+            block_lifetimes: flag(&root, "bklt")?,
+            block_accesses: flag(&root, "bkacc")?,
+            points,
+            frames,
+        })
+    }
+
+    /// Reads and parses the profile data file, e.g. `dhat.out.<pid>`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FormatError::Io`] - The file could not be read.
+    /// - [`FormatError::Syntax`] - The file is not valid JSON.
+    /// - [`FormatError::InvalidField`] - The file is not a DHAT profile.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Frames of the point's stack, innermost first, e.g.
+    /// `0x10A8F3: alloc::vec::Vec<T>::push (vec.rs:1829:13)`.
+    pub fn stack<'a>(&'a self, point: &'a ProgramPoint) -> impl Iterator<Item = &'a str> {
+        point.frames.iter().filter_map(|&frame| self.frames.get(frame).map(String::as_str))
+    }
+
+    /// Points with a frame containing `pattern`, e.g. a function name.
+    pub fn points_in<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = &'a ProgramPoint> {
+        self.points
+            .iter()
+            .filter(move |point| self.stack(point).any(|frame| frame.contains(pattern)))
+    }
+
+    /// `n` points with the largest `key`, largest first.
+    pub fn top_by<K: Ord>(&self, n: usize, key: impl Fn(&ProgramPoint) -> K) -> Vec<&ProgramPoint> {
+        let mut points: Vec<&ProgramPoint> = self.points.iter().collect();
+        points.sort_by_key(|point| core::cmp::Reverse(key(point)));
+        points.truncate(n);
+        points
+    }
+
+    /// `n` allocation sites with the most total bytes, largest first.
+    pub fn top_by_total_bytes(&self, n: usize) -> Vec<&ProgramPoint> {
+        self.top_by(n, |point| point.total_bytes)
+    }
+
+    /// Allocation sites never read from, only if [`block_accesses`](Profile::block_accesses)
+    /// were recorded.
+    pub fn never_read(&self) -> impl Iterator<Item = &ProgramPoint> {
+        self.points.iter().filter(|point| point.reads == Some(0))
+    }
+
+    /// Allocation sites never written to, only if [`block_accesses`](Profile::block_accesses)
+    /// were recorded.
+    pub fn never_written(&self) -> impl Iterator<Item = &ProgramPoint> {
+        self.points.iter().filter(|point| point.writes == Some(0))
+    }
+
+    /// Points with an access histogram of `size` bytes blocks, see
+    /// [`histogram`](super::histogram).
+    pub fn histograms(&self, size: usize) -> impl Iterator<Item = &ProgramPoint> {
+        self.points.iter().filter(move |point| point.accesses.as_ref().map(Vec::len) == Some(size))
+    }
+
+    /// Ad-hoc events recorded with [`event!`](super::event), aggregated per label.
+    pub fn events_by_label(&self) -> BTreeMap<&str, EventTotals> {
+        let mut labels: BTreeMap<&str, EventTotals> = BTreeMap::new();
+
+        for point in &self.points {
+            let Some(label) = self.stack(point).find_map(label) else { continue };
+            let totals = labels.entry(label).or_default();
+            totals.units += point.total_bytes;
+            totals.events += point.total_blocks;
+        }
+
+        labels
+    }
+
+    /// Totals of the ad-hoc events recorded with the `label`, zero if there are none.
+    pub fn events(&self, label: &str) -> EventTotals {
+        self.events_by_label().get(label).copied().unwrap_or_default()
+    }
+
+    /// Total bytes of all the points.
+    pub fn total_bytes(&self) -> u64 {
+        self.points.iter().map(|point| point.total_bytes).sum()
+    }
+
+    /// Total blocks of all the points.
+    pub fn total_blocks(&self) -> u64 {
+        self.points.iter().map(|point| point.total_blocks).sum()
+    }
+}
+
+impl ProgramPoint {
+    fn parse(value: &Value<'_>) -> Result<Self, FormatError> {
+        let frames = array(value, "fs")?
+            .iter()
+            .map(|frame| frame.as_u64().and_then(|frame| usize::try_from(frame).ok()))
+            .collect::<Option<_>>()
+            .ok_or(FormatError::InvalidField("fs"))?;
+        let total_bytes = number(value, "tb")?;
+        let total_blocks = number(value, "tbk")?;
+        // one count per byte of the blocks, which all have the same size
+        let size = total_bytes.checked_div(total_blocks).unwrap_or(0);
+        let accesses = match value.get("acc") {
+            Some(acc) => Some(decode_accesses(acc, size).ok_or(FormatError::InvalidField("acc"))?),
+            None => None,
+        };
+
+        Ok(Self {
+            total_bytes,
+            total_blocks,
+            total_lifetimes: optional(value, "tl")?,
+            max_bytes: optional(value, "mb")?,
+            max_blocks: optional(value, "mbk")?,
+            global_max_bytes: optional(value, "gb")?,
+            global_max_blocks: optional(value, "gbk")?,
+            end_bytes: optional(value, "fb")?,
+            end_blocks: optional(value, "fbk")?,
+            reads: optional(value, "rb")?,
+            writes: optional(value, "wb")?,
+            accesses,
+            frames,
+        })
+    }
+
+    /// Sum of the access counts of the bytes in `range`, e.g. the bytes of a struct field.
+    ///
+    /// Returns `None` if there is no histogram, or the range is out of the block.
+    pub fn accesses_in(&self, range: Range<usize>) -> Option<u64> {
+        Some(self.accesses.as_ref()?.get(range)?.iter().sum())
+    }
+
+    /// Average block size in bytes, `0` if there are no blocks.
+    pub fn average_size(&self) -> u64 {
+        self.total_bytes.checked_div(self.total_blocks).unwrap_or(0)
+    }
+}
+
+// Label of an `event!` frame, e.g. `0x10A8F3: app::main::__crabgrind_event_miss (main.rs:5:9)`.
+fn label(frame: &str) -> Option<&str> {
+    let start = frame.find(EVENT_PREFIX)? + EVENT_PREFIX.len();
+    let label = &frame[start..];
+    let end = label.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'));
+
+    Some(&label[..end.unwrap_or(label.len())])
+}
+
+// Access counts are run-length encoded, `-n, count` stands for `n` repeats of `count`.
+//
+// Fails if there are more counts than `size` bytes.
+fn decode_accesses(value: &Value<'_>, size: u64) -> Option<Vec<u64>> {
+    let mut counts = Vec::new();
+    let mut values = value.as_array()?.iter();
+    let mut remaining = size;
+
+    while let Some(value) = values.next() {
+        let n = value.as_i64()?;
+        if n < 0 {
+            let count = values.next()?.as_u64()?;
+            remaining = remaining.checked_sub(n.unsigned_abs())?;
+            counts.extend(core::iter::repeat(count).take(usize::try_from(n.unsigned_abs()).ok()?));
+        } else {
+            remaining = remaining.checked_sub(1)?;
+            counts.push(n.unsigned_abs());
+        }
+    }
+
+    Some(counts)
+}
+
+fn number(value: &Value<'_>, key: &'static str) -> Result<u64, FormatError> {
+    value.get(key).and_then(Value::as_u64).ok_or(FormatError::InvalidField(key))
+}
+
+fn optional(value: &Value<'_>, key: &'static str) -> Result<Option<u64>, FormatError> {
+    value.get(key).map(|n| n.as_u64().ok_or(FormatError::InvalidField(key))).transpose()
+}
+
+fn flag(value: &Value<'_>, key: &'static str) -> Result<bool, FormatError> {
+    value.get(key).map_or(Ok(false), |b| b.as_bool().ok_or(FormatError::InvalidField(key)))
+}
+
+fn string<'a>(value: &'a Value<'_>, key: &'static str) -> Result<&'a str, FormatError> {
+    value.get(key).and_then(Value::as_str).ok_or(FormatError::InvalidField(key))
+}
+
+fn array<'a, 'b>(value: &'a Value<'b>, key: &'static str) -> Result<&'a [Value<'b>], FormatError> {
+    value.get(key).and_then(Value::as_array).ok_or(FormatError::InvalidField(key))
+}
+
+impl From<io::Error> for FormatError {
+    #[inline(always)]
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl core::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Syntax { line, reason } => write!(f, "line {line}: {reason}"),
+            Self::InvalidField(field) => write!(f, "missing or invalid field `{field}`"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
@@ -8,7 +8,7 @@ use crate::bindings::CG_DRDClientRequest as CR;
 use core::{
     ffi::{CStr, c_char, c_void},
     marker::PhantomData,
-    mem::size_of,
+    mem::{size_of, size_of_val},
 };
 
 // Marker type for the "Suppressing" mode (`DRD_IGNORE_VAR`, `DRD_STOP_IGNORING_VAR`).
@@ -25,6 +25,20 @@ pub struct DRDSuppressing<'a, T>(PhantomData<&'a T>);
 #[derive(Debug)]
 pub struct DRDTracing<'a, T>(PhantomData<&'a T>);
 
+// Marker type for the "Suppressing" mode on a memory range.
+//
+// See [`ignore_slice`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DRDSuppressingRange<'a>(PhantomData<&'a ()>);
+
+// Marker type for the "Tracing" mode on a memory range.
+//
+// See [`trace_range`], [`trace_slice`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DRDTracingRange<'a>(PhantomData<&'a ()>);
+
 // Marker type for the "Ignoring Loads" mode (`ANNOTATE_IGNORE_READS_BEGIN`, `ANNOTATE_IGNORE_READS_END`).
 //
 // See [`annotate_ignore_reads`]
@@ -39,6 +53,21 @@ pub struct DRDIgnoringLoads;
 #[derive(Debug)]
 pub struct DRDIgnoringStores;
 
+// Marker type for an annotated barrier (`ANNOTATE_BARRIER_INIT`, `ANNOTATE_BARRIER_DESTROY`).
+//
+// See [`annotate_barrier`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DRDBarrier<'a, T>(PhantomData<&'a T>);
+
+// Marker type for an annotated producer-consumer queue (`ANNOTATE_PCQ_CREATE`,
+// `ANNOTATE_PCQ_DESTROY`).
+//
+// See [`annotate_pcq`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DRDQueue<'a, T>(PhantomData<&'a T>);
+
 impl<T> Scope for DRDTracing<'_, T> {
     type Inner = *const T;
 
@@ -67,6 +96,34 @@ impl<T> Scope for DRDSuppressing<'_, T> {
     }
 }
 
+impl Scope for DRDTracingRange<'_> {
+    type Inner = (*const c_void, usize);
+
+    #[inline(always)]
+    fn enter((addr, len): Self::Inner) {
+        client_request!(CR::CG_DRD_TRACE_VAR, addr, len);
+    }
+
+    #[inline(always)]
+    fn exit((addr, len): Self::Inner) {
+        client_request!(CR::CG_DRD_STOP_TRACING_VAR, addr, len);
+    }
+}
+
+impl Scope for DRDSuppressingRange<'_> {
+    type Inner = (*const c_void, usize);
+
+    #[inline(always)]
+    fn enter((addr, len): Self::Inner) {
+        client_request!(CR::CG_ANNOTATE_BENIGN_RACE_SIZED, addr, len);
+    }
+
+    #[inline(always)]
+    fn exit((addr, len): Self::Inner) {
+        client_request!(CR::CG_DRD_STOP_IGNORING_VAR, addr, len);
+    }
+}
+
 impl Scope for DRDIgnoringLoads {
     type Inner = *const c_void;
 
@@ -95,6 +152,62 @@ impl Scope for DRDIgnoringStores {
     }
 }
 
+// The addresses are kept as `usize`, so the guards can be shared between the threads.
+impl<T> Scope for DRDBarrier<'_, T> {
+    type Inner = (usize, usize);
+
+    #[inline(always)]
+    fn enter((barrier, count): Self::Inner) {
+        annotate_barrier_init(barrier as _, count, false);
+    }
+
+    #[inline(always)]
+    fn exit((barrier, _): Self::Inner) {
+        annotate_barrier_destroy(barrier as _);
+    }
+}
+
+impl<T> ScopeGuard<DRDBarrier<'_, T>> {
+    /// Runs the wait on the barrier `f` between `ANNOTATE_BARRIER_WAIT_BEFORE` and
+    /// `ANNOTATE_BARRIER_WAIT_AFTER`.
+    #[inline(always)]
+    pub fn wait<R>(&self, f: impl FnOnce() -> R) -> R {
+        let (barrier, _) = self.inner;
+        annotate_barrier_wait_before(barrier as _);
+        let result = f();
+        annotate_barrier_wait_after(barrier as _);
+        result
+    }
+}
+
+impl<T> Scope for DRDQueue<'_, T> {
+    type Inner = usize;
+
+    #[inline(always)]
+    fn enter(pcq: Self::Inner) {
+        annotate_pcq_create(pcq as _);
+    }
+
+    #[inline(always)]
+    fn exit(pcq: Self::Inner) {
+        annotate_pcq_destroy(pcq as _);
+    }
+}
+
+impl<T> ScopeGuard<DRDQueue<'_, T>> {
+    /// Annotates an element put into the queue, see [`annotate_pcq_put`].
+    #[inline(always)]
+    pub fn put(&self) {
+        annotate_pcq_put(self.inner as _);
+    }
+
+    /// Annotates an element taken from the queue, see [`annotate_pcq_get`].
+    #[inline(always)]
+    pub fn get(&self) {
+        annotate_pcq_get(self.inner as _);
+    }
+}
+
 #[doc = include_str!("../../doc/drd/valgrind_thread_id.md")]
 #[inline(always)]
 pub fn valgrind_threadid() -> ThreadId {
@@ -119,6 +232,24 @@ pub fn trace_var<T>(var: &T) -> ScopeGuard<DRDTracing<'_, T>> {
     ScopeGuard::new(var as _)
 }
 
+#[doc = include_str!("../../doc/drd/ignore_slice.md")]
+#[inline(always)]
+pub fn ignore_slice<T>(slice: &[T]) -> ScopeGuard<DRDSuppressingRange<'_>> {
+    ScopeGuard::new((slice.as_ptr().cast(), size_of_val(slice)))
+}
+
+#[doc = include_str!("../../doc/drd/trace_slice.md")]
+#[inline(always)]
+pub fn trace_slice<T>(slice: &[T]) -> ScopeGuard<DRDTracingRange<'_>> {
+    ScopeGuard::new((slice.as_ptr().cast(), size_of_val(slice)))
+}
+
+#[doc = include_str!("../../doc/drd/trace_range.md")]
+#[inline(always)]
+pub fn trace_range<'a>(ptr: *const c_void, len: usize) -> ScopeGuard<DRDTracingRange<'a>> {
+    ScopeGuard::new((ptr, len))
+}
+
 #[doc = include_str!("../../doc/drd/annotate_trace_memory.md")]
 #[inline(always)]
 pub fn annotate_trace_memory<'a>(addr: *const c_void) -> ScopeGuard<DRDTracing<'a, c_char>> {
@@ -161,7 +292,107 @@ pub fn annotate_thread_name(name: impl AsRef<CStr>) {
     client_request!(CR::CG_ANNOTATE_THREAD_NAME, name.as_ref().as_ptr());
 }
 
+#[doc = include_str!("../../doc/drd/annotate_barrier.md")]
+#[inline(always)]
+pub fn annotate_barrier<T>(barrier: &T, count: usize) -> ScopeGuard<DRDBarrier<'_, T>> {
+    ScopeGuard::new(((barrier as *const T) as usize, count))
+}
+
+#[doc = include_str!("../../doc/drd/annotate_barrier_init.md")]
+#[inline(always)]
+pub fn annotate_barrier_init(barrier: *const c_void, count: usize, reinitialization_allowed: bool) {
+    client_request!(CR::CG_ANNOTATE_BARRIER_INIT, barrier, count, reinitialization_allowed);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_barrier_destroy.md")]
+#[inline(always)]
+pub fn annotate_barrier_destroy(barrier: *const c_void) {
+    client_request!(CR::CG_ANNOTATE_BARRIER_DESTROY, barrier);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_barrier_wait_before.md")]
+#[inline(always)]
+pub fn annotate_barrier_wait_before(barrier: *const c_void) {
+    client_request!(CR::CG_ANNOTATE_BARRIER_WAIT_BEFORE, barrier);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_barrier_wait_after.md")]
+#[inline(always)]
+pub fn annotate_barrier_wait_after(barrier: *const c_void) {
+    client_request!(CR::CG_ANNOTATE_BARRIER_WAIT_AFTER, barrier);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_happens_before.md")]
+#[inline(always)]
+pub fn annotate_happens_before(obj: *const c_void) {
+    client_request!(CR::CG_DRD_ANNOTATE_HAPPENS_BEFORE, obj);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_happens_after.md")]
+#[inline(always)]
+pub fn annotate_happens_after(obj: *const c_void) {
+    client_request!(CR::CG_DRD_ANNOTATE_HAPPENS_AFTER, obj);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_condvar_signal.md")]
+#[inline(always)]
+pub fn annotate_condvar_signal(cv: *const c_void) {
+    let _ = cv;
+}
+
+#[doc = include_str!("../../doc/drd/annotate_condvar_wait.md")]
+#[inline(always)]
+pub fn annotate_condvar_wait(cv: *const c_void) {
+    let _ = cv;
+}
+
+#[doc = include_str!("../../doc/drd/annotate_pcq.md")]
+#[inline(always)]
+pub fn annotate_pcq<T>(queue: &T) -> ScopeGuard<DRDQueue<'_, T>> {
+    ScopeGuard::new((queue as *const T) as usize)
+}
+
+#[doc = include_str!("../../doc/drd/annotate_pcq_create.md")]
+#[inline(always)]
+pub fn annotate_pcq_create(pcq: *const c_void) {
+    let _ = pcq;
+}
+
+#[doc = include_str!("../../doc/drd/annotate_pcq_destroy.md")]
+#[inline(always)]
+pub fn annotate_pcq_destroy(pcq: *const c_void) {
+    let _ = pcq;
+}
+
+#[doc = include_str!("../../doc/drd/annotate_pcq_put.md")]
+#[inline(always)]
+pub fn annotate_pcq_put(pcq: *const c_void) {
+    annotate_happens_before(pcq);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_pcq_get.md")]
+#[inline(always)]
+pub fn annotate_pcq_get(pcq: *const c_void) {
+    annotate_happens_after(pcq);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_swap_memory_range.md")]
+#[inline(always)]
+pub fn annotate_swap_memory_range(addr: *const c_void, size: usize) {
+    annotate_new_memory(addr, size);
+}
+
+#[doc = include_str!("../../doc/drd/annotate_publish_memory_range.md")]
+#[inline(always)]
+pub fn annotate_publish_memory_range(addr: *const c_void, size: usize) {
+    let _ = (addr, size);
+}
+
+impl<T> Sealed for DRDBarrier<'_, T> {}
+impl<T> Sealed for DRDQueue<'_, T> {}
 impl<T> Sealed for DRDTracing<'_, T> {}
+impl Sealed for DRDTracingRange<'_> {}
+impl Sealed for DRDSuppressingRange<'_> {}
 impl<T> Sealed for DRDSuppressing<'_, T> {}
 impl Sealed for DRDIgnoringLoads {}
 impl Sealed for DRDIgnoringStores {}
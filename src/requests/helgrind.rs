@@ -1,10 +1,39 @@
 #![doc = include_str!("../../doc/helgrind.md")]
 use super::client_request;
+use crate::requests::{Scope, ScopeGuard, sealed::Sealed};
 
 #[cfg(feature = "valgrind")]
 use crate::bindings::CG_HelgrindClientRequest as CR;
 
-use core::ffi::c_void;
+pub mod monitor;
+
+use core::{
+    ffi::{CStr, c_void},
+    marker::PhantomData,
+    mem::size_of_val,
+};
+
+// Marker type for the "Disabled Checking" mode (`VALGRIND_HG_DISABLE_CHECKING`,
+// `VALGRIND_HG_ENABLE_CHECKING`).
+//
+// See [`disable_checking`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DisabledChecking<'a>(PhantomData<&'a ()>);
+
+impl Scope for DisabledChecking<'_> {
+    type Inner = (*const c_void, usize);
+
+    #[inline(always)]
+    fn enter((addr, size): Self::Inner) {
+        disable_checking_range(addr, size);
+    }
+
+    #[inline(always)]
+    fn exit((addr, size): Self::Inner) {
+        enable_checking_range(addr, size);
+    }
+}
 
 #[doc = include_str!("../../doc/helgrind/clean_memory.md")]
 #[inline(always)]
@@ -61,3 +90,64 @@ pub fn annotate_happens_after(addr: *const c_void) {
 pub fn annotate_happens_before_forget_all(addr: *const c_void) {
     client_request!(CR::CG_ANNOTATE_HAPPENS_BEFORE_FORGET_ALL, addr);
 }
+
+#[doc = include_str!("../../doc/helgrind/disable_checking.md")]
+#[inline(always)]
+pub fn disable_checking<T: ?Sized>(var: &T) -> ScopeGuard<DisabledChecking<'_>> {
+    ScopeGuard::new(((var as *const T).cast(), size_of_val(var)))
+}
+
+#[doc = include_str!("../../doc/helgrind/disable_checking_range.md")]
+#[inline(always)]
+pub fn disable_checking_range(addr: *const c_void, size: usize) {
+    client_request!(CR::CG_VALGRIND_HG_DISABLE_CHECKING, addr, size);
+}
+
+#[doc = include_str!("../../doc/helgrind/enable_checking_range.md")]
+#[inline(always)]
+pub fn enable_checking_range(addr: *const c_void, size: usize) {
+    client_request!(CR::CG_VALGRIND_HG_ENABLE_CHECKING, addr, size);
+}
+
+#[doc = include_str!("../../doc/helgrind/abits.md")]
+#[inline(always)]
+pub fn abits(addr: *const c_void, dest: &mut [u8]) -> Option<usize> {
+    match client_request!(
+        CR::CG_VALGRIND_HG_GET_ABITS,
+        usize::MAX,
+        addr,
+        dest.as_mut_ptr(),
+        dest.len(),
+        0,
+        0
+    ) {
+        usize::MAX => None,
+        addressable => Some(addressable),
+    }
+}
+
+#[doc = include_str!("../../doc/helgrind/annotate_benign_race.md")]
+#[inline(always)]
+pub fn annotate_benign_race<T: ?Sized>(var: &T) {
+    annotate_benign_race_sized((var as *const T).cast(), size_of_val(var));
+}
+
+#[doc = include_str!("../../doc/helgrind/annotate_benign_race_sized.md")]
+#[inline(always)]
+pub fn annotate_benign_race_sized(addr: *const c_void, size: usize) {
+    disable_checking_range(addr, size);
+}
+
+#[doc = include_str!("../../doc/helgrind/annotate_new_memory.md")]
+#[inline(always)]
+pub fn annotate_new_memory(addr: *const c_void, size: usize) {
+    clean_memory(addr, size);
+}
+
+#[doc = include_str!("../../doc/helgrind/annotate_thread_name.md")]
+#[inline(always)]
+pub fn annotate_thread_name(name: impl AsRef<CStr>) {
+    let _ = name;
+}
+
+impl Sealed for DisabledChecking<'_> {}
@@ -0,0 +1,65 @@
+#![doc = include_str!("../../../doc/helgrind/monitor.md")]
+use crate::requests::command::tool_commands;
+pub use crate::valgrind::monitor::{Command, XtMemoryCmd};
+
+use core::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+};
+
+/// `info locks` command builder.
+///
+/// Shows the state of the locks known to Helgrind, with their creation stacks.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct InfoLocksCmd {
+    lock: Option<*const c_void>,
+}
+
+impl InfoLocksCmd {
+    /// Shows all the locks(`info locks`).
+    #[inline(always)]
+    pub const fn all() -> Self {
+        Self { lock: None }
+    }
+
+    /// Shows only the lock at `addr`(`info locks <lock_addr>`).
+    #[inline(always)]
+    pub const fn lock(addr: *const c_void) -> Self {
+        Self { lock: Some(addr) }
+    }
+}
+
+/// `accesshistory` command builder.
+///
+/// Shows the stacks of the accesses to the range recorded by Helgrind, requires
+/// `--history-level=full`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct AccessHistoryCmd {
+    addr: *const c_void,
+    len: usize,
+}
+
+impl AccessHistoryCmd {
+    /// Access history of the range(`accesshistory <addr> <len>`).
+    #[inline(always)]
+    pub const fn new(addr: *const c_void, len: usize) -> Self {
+        Self { addr, len }
+    }
+}
+
+impl Display for InfoLocksCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.lock {
+            Some(addr) => write!(f, "info locks {addr:p}"),
+            None => f.write_str("info locks"),
+        }
+    }
+}
+
+impl Display for AccessHistoryCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "accesshistory {:p} {}", self.addr, self.len)
+    }
+}
+
+tool_commands!("helgrind": InfoLocksCmd, AccessHistoryCmd);
@@ -0,0 +1,65 @@
+#![doc = include_str!("../../doc/massif.md")]
+use super::{
+    command::{check_tool, send, token},
+    valgrind::MonitorError,
+};
+use crate::requests::{Scope, ScopeGuard, sealed::Sealed};
+
+use core::marker::PhantomData;
+
+// Marker type for the detailed snapshots taken around a region.
+//
+// See [`capture`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DetailedSnapshots<'a>(PhantomData<&'a str>);
+
+impl<'a> Scope for DetailedSnapshots<'a> {
+    type Inner = (&'a str, &'a str);
+
+    #[inline(always)]
+    fn enter((before, _): Self::Inner) {
+        let _ = detailed_snapshot(before);
+    }
+
+    #[inline(always)]
+    fn exit((_, after): Self::Inner) {
+        let _ = detailed_snapshot(after);
+    }
+}
+
+#[inline(always)]
+fn snapshot_command(command: &str, path: Option<&str>) -> Result<(), MonitorError> {
+    check_tool("massif")?;
+
+    match path {
+        Some(path) => send(format_args!("{command} {}", token(path)?)),
+        None => send(format_args!("{command}")),
+    }
+}
+
+#[doc = include_str!("../../doc/massif/snapshot.md")]
+#[inline(always)]
+pub fn snapshot<'a>(path: impl Into<Option<&'a str>>) -> Result<(), MonitorError> {
+    snapshot_command("snapshot", path.into())
+}
+
+#[doc = include_str!("../../doc/massif/detailed_snapshot.md")]
+#[inline(always)]
+pub fn detailed_snapshot<'a>(path: impl Into<Option<&'a str>>) -> Result<(), MonitorError> {
+    snapshot_command("detailed_snapshot", path.into())
+}
+
+#[doc = include_str!("../../doc/massif/all_snapshots.md")]
+#[inline(always)]
+pub fn all_snapshots<'a>(path: impl Into<Option<&'a str>>) -> Result<(), MonitorError> {
+    snapshot_command("all_snapshots", path.into())
+}
+
+#[doc = include_str!("../../doc/massif/capture.md")]
+#[inline(always)]
+pub fn capture<'a>(before: &'a str, after: &'a str) -> ScopeGuard<DetailedSnapshots<'a>> {
+    ScopeGuard::new((before, after))
+}
+
+impl Sealed for DetailedSnapshots<'_> {}
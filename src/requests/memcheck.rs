@@ -12,6 +12,8 @@ use core::{
 #[cfg(feature = "valgrind")]
 use crate::bindings::CG_MemcheckClientRequest as CR;
 
+pub mod monitor;
+
 /// Identifier for a custom memory block description.
 ///
 /// Returned by [`create_block`] and used to remove the association with [`discard_block`]
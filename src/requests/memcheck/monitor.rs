@@ -0,0 +1,522 @@
+#![doc = include_str!("../../../doc/memcheck/monitor.md")]
+use super::MemState;
+use crate::requests::command::tool_commands;
+use crate::valgrind::MonitorError;
+pub use crate::valgrind::monitor::{Command, XtMemoryCmd};
+
+use core::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+};
+
+/// Kind of a leaked block, as classified by the Memcheck leak search.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum LeakKind {
+    /// No pointer to the block found.
+    Definite,
+    /// The block is pointed to only by other leaked blocks.
+    Indirect,
+    /// Only interior pointers to the block found.
+    Possible,
+    /// A pointer to the start of the block found.
+    Reachable,
+}
+
+/// Heuristic used by the leak search to recognize interior pointers as valid.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum LeakHeuristic {
+    /// Pointers to the data of a C++ `std::string`.
+    StdString,
+    /// Pointers to a block prefixed with a 64-bit length.
+    Length64,
+    /// Pointers to a C++ `new[]` array prefixed with its length.
+    NewArray,
+    /// Pointers to the inner objects of a C++ object with multiple inheritance.
+    MultipleInheritance,
+}
+
+/// Subset of loss records reported by a leak search, relative to the previous one.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum LeakDelta {
+    /// Only the new loss records.
+    New,
+    /// Only the loss records which increased.
+    #[default]
+    Increased,
+    /// Only the loss records which changed.
+    Changed,
+    /// All the loss records.
+    Any,
+}
+
+/// Level of details of a leak search.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum LeakMode {
+    /// Every loss record with its allocation stack.
+    #[default]
+    Full,
+    /// Only the leak summary.
+    Summary,
+    /// A leak report in the Valgrind "xtree" format.
+    XtLeak,
+}
+
+// Maximum number of loss records or blocks to output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum Limit {
+    Limited(usize),
+    Unlimited,
+}
+
+// `kinds` and `heuristics` sets encoded as bitmasks over the enum discriminants.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+struct Set(u8);
+
+// Collects the enum discriminants into a `Set` bitmask.
+macro_rules! set_of {
+    ($items:expr) => {{
+        let items = $items;
+        let mut set = 0u8;
+        let mut i = 0;
+        while i < items.len() {
+            set |= 1 << items[i] as u8;
+            i += 1;
+        }
+        Set(set)
+    }};
+}
+
+impl Set {
+    fn write<T: Copy>(
+        self,
+        f: &mut Formatter<'_>,
+        all: &[T],
+        name: fn(T) -> &'static str,
+    ) -> fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("none");
+        }
+        if self.0.count_ones() as usize == all.len() {
+            return f.write_str("all");
+        }
+
+        let mut sep = "";
+        for (i, item) in all.iter().enumerate() {
+            if self.0 & (1 << i) != 0 {
+                write!(f, "{sep}{}", name(*item))?;
+                sep = ",";
+            }
+        }
+        Ok(())
+    }
+}
+
+const LEAK_KINDS: [LeakKind; 4] =
+    [LeakKind::Definite, LeakKind::Indirect, LeakKind::Possible, LeakKind::Reachable];
+
+const LEAK_HEURISTICS: [LeakHeuristic; 4] = [
+    LeakHeuristic::StdString,
+    LeakHeuristic::Length64,
+    LeakHeuristic::NewArray,
+    LeakHeuristic::MultipleInheritance,
+];
+
+impl LeakKind {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Definite => "definite",
+            Self::Indirect => "indirect",
+            Self::Possible => "possible",
+            Self::Reachable => "reachable",
+        }
+    }
+}
+
+impl LeakHeuristic {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::StdString => "stdstring",
+            Self::Length64 => "length64",
+            Self::NewArray => "newarray",
+            Self::MultipleInheritance => "multipleinheritance",
+        }
+    }
+}
+
+/// `leak_check` command builder.
+///
+/// Only the explicitly configured options are sent, the rest fall back to Memcheck defaults:
+/// `full possibleleak increased unlimited`.
+///
+/// ```rust, no_run
+/// use crabgrind::memcheck::monitor::{Command, LeakCheckCmd, LeakKind::*};
+///
+/// LeakCheckCmd::full().kinds(&[Definite, Possible]).limited(20).increase().run().unwrap();
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LeakCheckCmd {
+    mode: LeakMode,
+    kinds: Option<Set>,
+    heuristics: Option<Set>,
+    delta: Option<LeakDelta>,
+    limit: Option<Limit>,
+}
+
+impl LeakCheckCmd {
+    /// Leak search with the given level of details.
+    #[inline(always)]
+    pub const fn new(mode: LeakMode) -> Self {
+        Self { mode, kinds: None, heuristics: None, delta: None, limit: None }
+    }
+
+    /// Leak search reporting every loss record(`leak_check full`).
+    #[inline(always)]
+    pub const fn full() -> Self {
+        Self::new(LeakMode::Full)
+    }
+
+    /// Leak search reporting only the summary(`leak_check summary`).
+    #[inline(always)]
+    pub const fn summary() -> Self {
+        Self::new(LeakMode::Summary)
+    }
+
+    /// Leak search producing an "xtree" leak report(`leak_check xtleak`).
+    #[inline(always)]
+    pub const fn xtleak() -> Self {
+        Self::new(LeakMode::XtLeak)
+    }
+
+    /// Kinds of leaks to report(`kinds <set>`). An empty set reports none.
+    #[inline(always)]
+    #[must_use]
+    pub const fn kinds(mut self, kinds: &[LeakKind]) -> Self {
+        self.kinds = Some(set_of!(kinds));
+        self
+    }
+
+    /// Heuristics used to recognize interior pointers(`heuristics <set>`). An empty set disables
+    /// all of them.
+    #[inline(always)]
+    #[must_use]
+    pub const fn heuristics(mut self, heuristics: &[LeakHeuristic]) -> Self {
+        self.heuristics = Some(set_of!(heuristics));
+        self
+    }
+
+    /// Subset of loss records to report, relative to the previous leak search.
+    #[inline(always)]
+    #[must_use]
+    pub const fn delta(mut self, delta: LeakDelta) -> Self {
+        self.delta = Some(delta);
+        self
+    }
+
+    /// Reports only the loss records which increased since the previous leak search.
+    #[inline(always)]
+    #[must_use]
+    pub const fn increase(self) -> Self {
+        self.delta(LeakDelta::Increased)
+    }
+
+    /// Reports only the loss records which changed since the previous leak search.
+    #[inline(always)]
+    #[must_use]
+    pub const fn changed(self) -> Self {
+        self.delta(LeakDelta::Changed)
+    }
+
+    /// Reports only the new loss records since the previous leak search.
+    #[inline(always)]
+    #[must_use]
+    pub const fn new_only(self) -> Self {
+        self.delta(LeakDelta::New)
+    }
+
+    /// Reports all the loss records.
+    #[inline(always)]
+    #[must_use]
+    pub const fn any(self) -> Self {
+        self.delta(LeakDelta::Any)
+    }
+
+    /// Reports at most `max` loss records(`limited <max_loss_records_output>`).
+    #[inline(always)]
+    #[must_use]
+    pub const fn limited(mut self, max: usize) -> Self {
+        self.limit = Some(Limit::Limited(max));
+        self
+    }
+
+    /// Reports all the loss records(`unlimited`).
+    #[inline(always)]
+    #[must_use]
+    pub const fn unlimited(mut self) -> Self {
+        self.limit = Some(Limit::Unlimited);
+        self
+    }
+}
+
+/// `block_list` command builder.
+///
+/// Lists the blocks of the loss records reported by the latest leak search.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct BlockListCmd {
+    from: usize,
+    to: usize,
+    limit: Option<Limit>,
+    heuristics: Option<Set>,
+}
+
+impl BlockListCmd {
+    /// Blocks of a single loss record(`block_list <loss_record_nr>`).
+    #[inline(always)]
+    pub const fn record(nr: usize) -> Self {
+        Self { from: nr, to: nr, limit: None, heuristics: None }
+    }
+
+    /// Blocks of the range of loss records(`block_list <from>..<to>`).
+    ///
+    /// # Errors
+    ///
+    /// - [`MonitorError::InvalidCommand`] - The range is reversed, e.g. `5..=2`.
+    #[inline]
+    pub const fn records(records: RangeInclusive<usize>) -> Result<Self, MonitorError> {
+        let (from, to) = (*records.start(), *records.end());
+        if from > to {
+            return Err(MonitorError::InvalidCommand);
+        }
+        Ok(Self { from, to, limit: None, heuristics: None })
+    }
+
+    /// Lists at most `max` blocks(`limited <max_blocks>`).
+    #[inline(always)]
+    #[must_use]
+    pub const fn limited(mut self, max: usize) -> Self {
+        self.limit = Some(Limit::Limited(max));
+        self
+    }
+
+    /// Lists all the blocks(`unlimited`).
+    #[inline(always)]
+    #[must_use]
+    pub const fn unlimited(mut self) -> Self {
+        self.limit = Some(Limit::Unlimited);
+        self
+    }
+
+    /// Lists only the blocks found reachable via the given heuristics(`heuristics <set>`).
+    #[inline(always)]
+    #[must_use]
+    pub const fn heuristics(mut self, heuristics: &[LeakHeuristic]) -> Self {
+        self.heuristics = Some(set_of!(heuristics));
+        self
+    }
+}
+
+/// Which property [`CheckMemoryCmd`] verifies.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum MemCheck {
+    /// The range is addressable.
+    Addressable,
+    /// The range is addressable and defined.
+    Defined,
+}
+
+/// `check_memory` command builder.
+///
+/// Verifies the range and reports the first offending byte, with the block it belongs to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct CheckMemoryCmd {
+    check: MemCheck,
+    addr: *const c_void,
+    len: usize,
+}
+
+impl CheckMemoryCmd {
+    /// Verifies that the range is addressable(`check_memory addressable <addr> <len>`).
+    #[inline(always)]
+    pub const fn addressable(addr: *const c_void, len: usize) -> Self {
+        Self { check: MemCheck::Addressable, addr, len }
+    }
+
+    /// Verifies that the range is defined(`check_memory defined <addr> <len>`).
+    #[inline(always)]
+    pub const fn defined(addr: *const c_void, len: usize) -> Self {
+        Self { check: MemCheck::Defined, addr, len }
+    }
+}
+
+/// `make_memory` command builder.
+///
+/// The monitor command counterpart of [`mark_memory`](super::mark_memory).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MakeMemoryCmd {
+    state: MemState,
+    addr: *const c_void,
+    len: usize,
+}
+
+impl MakeMemoryCmd {
+    /// Marks the range with the given state(`make_memory <state> <addr> <len>`).
+    #[inline(always)]
+    pub const fn new(state: MemState, addr: *const c_void, len: usize) -> Self {
+        Self { state, addr, len }
+    }
+}
+
+/// `get_vbits` command builder.
+///
+/// Shows the validity bits of the range, one hex digit pair per byte.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct GetVBitsCmd {
+    addr: *const c_void,
+    len: usize,
+}
+
+impl GetVBitsCmd {
+    /// Validity bits of the range(`get_vbits <addr> <len>`).
+    #[inline(always)]
+    pub const fn new(addr: *const c_void, len: usize) -> Self {
+        Self { addr, len }
+    }
+}
+
+/// `xb` command builder.
+///
+/// Shows the validity bits of the range along with the byte values.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct XbCmd {
+    addr: *const c_void,
+    len: usize,
+}
+
+impl XbCmd {
+    /// Validity bits and values of the range(`xb <addr> <len>`).
+    #[inline(always)]
+    pub const fn new(addr: *const c_void, len: usize) -> Self {
+        Self { addr, len }
+    }
+}
+
+/// `who_points_at` command builder.
+///
+/// Shows the locations pointing at the range, including interior pointers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct WhoPointsAtCmd {
+    addr: *const c_void,
+    len: usize,
+}
+
+impl WhoPointsAtCmd {
+    /// Locations pointing at `addr`, or inside `addr..addr + len`(`who_points_at <addr> <len>`).
+    #[inline(always)]
+    pub const fn new(addr: *const c_void, len: usize) -> Self {
+        Self { addr, len }
+    }
+}
+
+impl Display for LeakCheckCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.mode {
+            LeakMode::Full => "leak_check full",
+            LeakMode::Summary => "leak_check summary",
+            LeakMode::XtLeak => "leak_check xtleak",
+        })?;
+
+        if let Some(kinds) = self.kinds {
+            f.write_str(" kinds ")?;
+            kinds.write(f, &LEAK_KINDS, LeakKind::name)?;
+        }
+        if let Some(heuristics) = self.heuristics {
+            f.write_str(" heuristics ")?;
+            heuristics.write(f, &LEAK_HEURISTICS, LeakHeuristic::name)?;
+        }
+        if let Some(delta) = self.delta {
+            f.write_str(match delta {
+                LeakDelta::New => " new",
+                LeakDelta::Increased => " increased",
+                LeakDelta::Changed => " changed",
+                LeakDelta::Any => " any",
+            })?;
+        }
+        match self.limit {
+            Some(Limit::Limited(max)) => write!(f, " limited {max}"),
+            Some(Limit::Unlimited) => f.write_str(" unlimited"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for BlockListCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { from, to, .. } = self;
+
+        if from == to {
+            write!(f, "block_list {from}")?;
+        } else {
+            write!(f, "block_list {from}..{to}")?;
+        }
+        match self.limit {
+            Some(Limit::Limited(max)) => write!(f, " limited {max}")?,
+            Some(Limit::Unlimited) => f.write_str(" unlimited")?,
+            None => {}
+        }
+        if let Some(heuristics) = self.heuristics {
+            f.write_str(" heuristics ")?;
+            heuristics.write(f, &LEAK_HEURISTICS, LeakHeuristic::name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for CheckMemoryCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let check = match self.check {
+            MemCheck::Addressable => "addressable",
+            MemCheck::Defined => "defined",
+        };
+        write!(f, "check_memory {check} {:p} {}", self.addr, self.len)
+    }
+}
+
+impl Display for MakeMemoryCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = match self.state {
+            MemState::NoAccess => "noaccess",
+            MemState::Undefined => "undefined",
+            MemState::Defined => "defined",
+            MemState::DefinedIfAddressable => "Definedifaddressable",
+        };
+        write!(f, "make_memory {state} {:p} {}", self.addr, self.len)
+    }
+}
+
+impl Display for GetVBitsCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "get_vbits {:p} {}", self.addr, self.len)
+    }
+}
+
+impl Display for XbCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "xb {:p} {}", self.addr, self.len)
+    }
+}
+
+impl Display for WhoPointsAtCmd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "who_points_at {:p} {}", self.addr, self.len)
+    }
+}
+
+tool_commands!("memcheck":
+    LeakCheckCmd,
+    BlockListCmd,
+    CheckMemoryCmd,
+    MakeMemoryCmd,
+    GetVBitsCmd,
+    XbCmd,
+    WhoPointsAtCmd,
+);
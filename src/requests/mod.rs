@@ -3,9 +3,13 @@ pub mod callgrind;
 pub mod dhat;
 pub mod drd;
 pub mod helgrind;
+pub mod massif;
 pub mod memcheck;
+#[cfg(target_has_atomic = "8")]
+pub mod race;
 pub mod valgrind;
 
+pub(crate) mod command;
 pub(crate) mod constants;
 
 macro_rules! client_request {
@@ -118,6 +122,52 @@ impl<S: Scope> Drop for ScopeGuard<S> {
     }
 }
 
+// Nesting depth of a process-wide state, e.g. instrumentation, toggled by the outermost scope.
+//
+// The lock is held across the depth update and the toggling request, so that a thread entering
+// the first scope can't run its request before another thread leaving the last one.
+#[cfg(target_has_atomic = "ptr")]
+pub(crate) struct SharedDepth {
+    lock: crate::sync::SpinMutex,
+    depth: core::sync::atomic::AtomicUsize,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl SharedDepth {
+    pub(crate) const fn new() -> Self {
+        Self { lock: crate::sync::SpinMutex::new(), depth: core::sync::atomic::AtomicUsize::new(0) }
+    }
+
+    // Calls `start` when entering the outermost scope.
+    #[inline(always)]
+    pub(crate) fn enter(&self, start: impl FnOnce()) {
+        self.locked(|depth| {
+            if depth.fetch_add(1, core::sync::atomic::Ordering::Relaxed) == 0 {
+                start();
+            }
+        });
+    }
+
+    // Calls `stop` when leaving the outermost scope.
+    #[inline(always)]
+    pub(crate) fn exit(&self, stop: impl FnOnce()) {
+        self.locked(|depth| {
+            if depth.fetch_sub(1, core::sync::atomic::Ordering::Relaxed) == 1 {
+                stop();
+            }
+        });
+    }
+
+    #[inline(always)]
+    fn locked(&self, f: impl FnOnce(&core::sync::atomic::AtomicUsize)) {
+        use crate::sync::RawLock;
+
+        self.lock.lock();
+        f(&self.depth);
+        self.lock.unlock();
+    }
+}
+
 mod sealed {
     pub trait Sealed {}
 }
@@ -0,0 +1,177 @@
+#![doc = include_str!("../../doc/race.md")]
+use super::{
+    drd, helgrind,
+    valgrind::{self, RunningMode},
+};
+use crate::requests::{Scope, ScopeGuard, sealed::Sealed};
+
+use core::{
+    ffi::{CStr, c_void},
+    mem::size_of_val,
+    ops::Range,
+    ptr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Race detector the program runs under.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Detector {
+    /// `--tool=helgrind`
+    Helgrind,
+    /// `--tool=drd`
+    DRD,
+}
+
+// `TOOL` states
+const UNDETECTED: u8 = 0;
+const NONE: u8 = 1;
+const HELGRIND: u8 = 2;
+const DRD: u8 = 3;
+// Under Valgrind, but the tool name is unavailable: both tools' requests are sent. DRD also
+// implements Helgrind's happens-before/after codes, so those are sent once, in Helgrind's encoding.
+const UNKNOWN: u8 = 4;
+
+// The tool doesn't change during the run, so it's detected once.
+static TOOL: AtomicU8 = AtomicU8::new(UNDETECTED);
+
+fn tool() -> u8 {
+    match TOOL.load(Ordering::Relaxed) {
+        UNDETECTED => {
+            let tool = detect();
+            TOOL.store(tool, Ordering::Relaxed);
+            tool
+        }
+        tool => tool,
+    }
+}
+
+fn detect() -> u8 {
+    if valgrind::running_mode() == RunningMode::Native {
+        return NONE;
+    }
+    // `VALGRIND_GET_TOOLNAME` is missing from the older headers
+    if crate::VALGRIND_VERSION < (3, 27) {
+        return UNKNOWN;
+    }
+
+    let mut buf = [0; 64];
+    match valgrind::toolname(&mut buf).map(CStr::to_bytes) {
+        Some(b"helgrind") => HELGRIND,
+        Some(b"drd") => DRD,
+        Some(_) => NONE,
+        None => UNKNOWN,
+    }
+}
+
+#[inline(always)]
+fn helgrind() -> bool {
+    matches!(tool(), HELGRIND | UNKNOWN)
+}
+
+#[inline(always)]
+fn drd() -> bool {
+    matches!(tool(), DRD | UNKNOWN)
+}
+
+// Marker type for the "Ignoring Accesses" mode (`ANNOTATE_IGNORE_READS_BEGIN`,
+// `ANNOTATE_IGNORE_WRITES_BEGIN` and their `_END`).
+//
+// See [`ignore_accesses`]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct IgnoringAccesses;
+
+impl Scope for IgnoringAccesses {
+    // Whether DRD is ignoring the accesses
+    type Inner = bool;
+
+    #[inline(always)]
+    fn enter(ignoring: Self::Inner) {
+        if ignoring {
+            drd::DRDIgnoringLoads::enter(ptr::null());
+            drd::DRDIgnoringStores::enter(ptr::null());
+        }
+    }
+
+    #[inline(always)]
+    fn exit(ignoring: Self::Inner) {
+        if ignoring {
+            drd::DRDIgnoringStores::exit(ptr::null());
+            drd::DRDIgnoringLoads::exit(ptr::null());
+        }
+    }
+}
+
+#[doc = include_str!("../../doc/race/detector.md")]
+#[inline]
+pub fn detector() -> Option<Detector> {
+    match tool() {
+        HELGRIND => Some(Detector::Helgrind),
+        DRD => Some(Detector::DRD),
+        _ => None,
+    }
+}
+
+#[doc = include_str!("../../doc/race/benign.md")]
+#[inline]
+pub fn benign<T: ?Sized>(var: &T) {
+    let (addr, size) = ((var as *const T).cast::<c_void>(), size_of_val(var));
+    if helgrind() {
+        helgrind::annotate_benign_race_sized(addr, size);
+    }
+    if drd() {
+        drd::annotate_benign_race_sized(addr, size);
+    }
+}
+
+#[doc = include_str!("../../doc/race/new_memory.md")]
+#[inline]
+pub fn new_memory(range: Range<*const u8>) {
+    // a reversed range is empty
+    let (addr, size) =
+        (range.start.cast(), (range.end as usize).saturating_sub(range.start as usize));
+    if helgrind() {
+        helgrind::annotate_new_memory(addr, size);
+    }
+    if drd() {
+        drd::annotate_new_memory(addr, size);
+    }
+}
+
+#[doc = include_str!("../../doc/race/thread_name.md")]
+#[inline]
+pub fn thread_name(name: impl AsRef<CStr>) {
+    if drd() {
+        drd::annotate_thread_name(name);
+    }
+}
+
+#[doc = include_str!("../../doc/race/ignore_accesses.md")]
+#[inline]
+pub fn ignore_accesses() -> ScopeGuard<IgnoringAccesses> {
+    ScopeGuard::new(drd())
+}
+
+#[doc = include_str!("../../doc/race/happens_before.md")]
+#[inline]
+pub fn happens_before(addr: *const c_void) {
+    // same request code for both tools, see `UNKNOWN`
+    if helgrind() {
+        helgrind::annotate_happens_before(addr);
+    } else if drd() {
+        drd::annotate_happens_before(addr);
+    }
+}
+
+#[doc = include_str!("../../doc/race/happens_after.md")]
+#[inline]
+pub fn happens_after(addr: *const c_void) {
+    // same request code for both tools, see `UNKNOWN`
+    if helgrind() {
+        helgrind::annotate_happens_after(addr);
+    } else if drd() {
+        drd::annotate_happens_after(addr);
+    }
+}
+
+impl Sealed for IgnoringAccesses {}
@@ -10,10 +10,23 @@ use crate::bindings::CG_ValgrindClientRequest as CR;
 
 use core::ffi::{CStr, c_int, c_void};
 
+#[cfg(feature = "std")]
+pub mod capture;
+pub mod monitor;
+#[cfg(feature = "std")]
+mod reexec;
+
+#[cfg(feature = "std")]
+pub(crate) use reexec::temp_file;
+#[cfg(feature = "std")]
+pub use reexec::{LogDestination, REEXEC_ENV, ToolConfig, reexec};
+
 /// Designates the pool as a "meta-pool". See [`create_mempool`]
 pub const VALGRIND_MEMPOOL_AUTO_FREE: u8 = 1;
 /// Automatically free all second-level blocks. See [`create_mempool`]
 pub const VALGRIND_MEMPOOL_METAPOOL: u8 = 2;
+/// Maximum length(including the terminating nul) of the monitor commands issued by typed wrappers.
+pub const MONITOR_COMMAND_MAX: usize = 1024;
 
 #[doc(hidden)]
 #[derive(Debug)]
@@ -42,6 +55,18 @@ pub type ThreadId = usize;
 /// Valgrind internal address range identifier returned by [`stack_register`]
 pub type StackId = usize;
 
+/// Typed monitor command errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum MonitorError {
+    /// The command was not recognized by the Valgrind core or the running tool.
+    NotFound,
+    /// The command belongs to another tool than the running one.
+    WrongTool,
+    /// The command doesn't fit [`MONITOR_COMMAND_MAX`], or an argument is malformed(e.g. contains
+    /// whitespaces or nul bytes).
+    InvalidCommand,
+}
+
 #[doc = include_str!("../../doc/valgrind/RunningMode.md")]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
 pub enum RunningMode {
@@ -295,4 +320,19 @@ pub fn toolname(buf: &mut [u8; 64]) -> Option<&CStr> {
     }
 }
 
+impl core::fmt::Display for MonitorError {
+    #[inline(always)]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "monitor command not recognized"),
+            Self::WrongTool => write!(f, "monitor command belongs to another tool"),
+            Self::InvalidCommand => write!(f, "malformed monitor command"),
+        }
+    }
+}
+
+// `has_core_error` is set by build.rs
+#[cfg(has_core_error)]
+impl core::error::Error for MonitorError {}
+
 impl Sealed for DisabledReporting {}
@@ -0,0 +1,333 @@
+#![doc = include_str!("../../../doc/valgrind/capture.md")]
+use super::{MonitorError, monitor::Command};
+
+use std::{
+    env,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::PathBuf,
+    string::String,
+    vec::Vec,
+};
+
+/// Environment variable holding the path of the captured Valgrind log.
+///
+/// Set by [`reexec`](super::reexec) with [`LogDestination::Captured`](super::LogDestination::Captured).
+pub const LOG_FILE_ENV: &str = "CRABGRIND_LOG_FILE";
+
+/// Log capturing errors.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// The process was not started with a captured log. See [`reexec`](super::reexec)
+    NotCaptured,
+    /// The monitor command failed.
+    Monitor(MonitorError),
+    /// The log could not be read.
+    Io(io::Error),
+}
+
+/// Reader of the captured Valgrind log.
+///
+/// Returns only the text written to the log since the reader was opened, or since the previous
+/// [`read`](Log::read).
+#[derive(Debug)]
+pub struct Log {
+    file: File,
+}
+
+impl Log {
+    /// Opens the captured log, positioned at its current end.
+    ///
+    /// # Errors
+    ///
+    /// - [`CaptureError::NotCaptured`] - [`LOG_FILE_ENV`] is not set.
+    /// - [`CaptureError::Io`] - The log could not be opened.
+    pub fn open() -> Result<Self, CaptureError> {
+        let path = env::var_os(LOG_FILE_ENV).map(PathBuf::from).ok_or(CaptureError::NotCaptured)?;
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::End(0))?;
+
+        Ok(Self { file })
+    }
+
+    /// Reads the text appended to the log since the last read, with the `==<pid>==` line
+    /// prefixes stripped.
+    ///
+    /// # Errors
+    ///
+    /// - [`CaptureError::Io`] - The log could not be read.
+    pub fn read(&mut self) -> Result<String, CaptureError> {
+        let mut bytes = Vec::new();
+        self.file.read_to_end(&mut bytes)?;
+
+        Ok(strip_prefixes(&String::from_utf8_lossy(&bytes)))
+    }
+}
+
+/// Runs `f` and returns the text it made Valgrind write to the log.
+///
+/// # Errors
+///
+/// See [`Log::open`], [`Log::read`]
+pub fn capture<R>(f: impl FnOnce() -> R) -> Result<(R, String), CaptureError> {
+    let mut log = Log::open()?;
+    let ret = f();
+    Ok((ret, log.read()?))
+}
+
+/// Runs the monitor command and returns its output.
+///
+/// The command output is captured only if it goes to the Valgrind log, i.e. no GDB is connected
+/// or the output mode is
+/// [`OutputMode::Log`](crate::valgrind::monitor::OutputMode::Log).
+///
+/// # Errors
+///
+/// - [`CaptureError::Monitor`] - The command failed. See [`Command::run`]
+/// - [`CaptureError::NotCaptured`], [`CaptureError::Io`] - See [`Log::open`], [`Log::read`]
+pub fn monitor_output(cmd: &impl Command) -> Result<String, CaptureError> {
+    let (result, text) = capture(|| cmd.run())?;
+    result?;
+    Ok(text)
+}
+
+// Valgrind prefixes the log lines with `==<pid>== `, monitor commands output is written as is.
+fn strip_prefixes(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            line.strip_prefix("==")
+                .and_then(|rest| rest.split_once("=="))
+                .filter(|(pid, _)| pid.bytes().all(|b| b.is_ascii_digit()))
+                .map_or(line, |(_, rest)| rest.strip_prefix(' ').unwrap_or(rest))
+        })
+        .fold(String::new(), |mut text, line| {
+            text.push_str(line);
+            text.push('\n');
+            text
+        })
+}
+
+// Parses a number formatted by Valgrind, e.g. `1,234`.
+fn number(s: &str) -> Option<u64> {
+    if s.is_empty() {
+        return None;
+    }
+
+    s.bytes().filter(|&b| b != b',').try_fold(0u64, |n, b| {
+        let digit = b.is_ascii_digit().then(|| u64::from(b - b'0'))?;
+        n.checked_mul(10)?.checked_add(digit)
+    })
+}
+
+// Returns the word following `key` in `line`.
+fn value_after<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let mut words = line.split_whitespace();
+    words.by_ref().find(|&w| w == key)?;
+    words.next()
+}
+
+/// Bytes and blocks of a leak category.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Leaked {
+    /// Leaked bytes.
+    pub bytes: u64,
+    /// Leaked blocks.
+    pub blocks: u64,
+}
+
+/// Memcheck `LEAK SUMMARY`, as reported by a leak search.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct LeakSummary {
+    /// No pointer to the blocks found.
+    pub definitely_lost: Leaked,
+    /// Blocks pointed to only by other leaked blocks.
+    pub indirectly_lost: Leaked,
+    /// Only interior pointers to the blocks found.
+    pub possibly_lost: Leaked,
+    /// Pointers to the start of the blocks found.
+    pub still_reachable: Leaked,
+    /// Leaks matching a suppression.
+    pub suppressed: Leaked,
+}
+
+impl LeakSummary {
+    /// Parses the last leak summary found in `text`.
+    ///
+    /// A leak search that found no heap blocks at all("All heap blocks were freed") yields an
+    /// all-zero summary.
+    pub fn parse(text: &str) -> Option<Self> {
+        let start = match (text.rfind("LEAK SUMMARY:"), text.rfind("All heap blocks were freed")) {
+            (Some(summary), Some(freed)) if freed > summary => return Some(Self::default()),
+            (Some(summary), _) => summary,
+            (None, Some(_)) => return Some(Self::default()),
+            (None, None) => return None,
+        };
+
+        let mut summary = Self::default();
+        for line in text[start..].lines().skip(1) {
+            let Some((category, counts)) = line.split_once(':') else { break };
+            // the text may come straight from the log, with the `==<pid>==` prefixes
+            let leaked = match category.rsplit("==").next().unwrap_or(category).trim() {
+                "definitely lost" => &mut summary.definitely_lost,
+                "indirectly lost" => &mut summary.indirectly_lost,
+                "possibly lost" => &mut summary.possibly_lost,
+                "still reachable" => &mut summary.still_reachable,
+                "suppressed" => &mut summary.suppressed,
+                _ => continue,
+            };
+
+            // `<bytes> [(+delta)] bytes in <blocks> [(+delta)] blocks`
+            let mut numbers = counts.split_whitespace().filter_map(number);
+            leaked.bytes = numbers.next()?;
+            leaked.blocks = numbers.next()?;
+        }
+
+        Some(summary)
+    }
+}
+
+/// Error counters, as reported by
+/// [`monitor::n_errs_found`](crate::valgrind::monitor::n_errs_found).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct ErrorCount {
+    /// Errors found so far.
+    pub found: u64,
+    /// Errors shown so far, i.e. not suppressed.
+    pub shown: u64,
+}
+
+impl ErrorCount {
+    /// Parses the last `v.info n_errs_found` output found in `text`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let line = text.lines().rev().find(|line| line.contains("n_errs_found"))?;
+
+        Some(Self {
+            found: number(value_after(line, "n_errs_found")?)?,
+            shown: number(value_after(line, "n_errs_shown")?)?,
+        })
+    }
+}
+
+/// Helgrind lock kind.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub enum LockKind {
+    /// Non-recursive mutex.
+    NonRecursive,
+    /// Recursive mutex.
+    Recursive,
+    /// Reader-writer lock.
+    ReadWrite,
+    /// Kind unknown to this parser.
+    Other(String),
+}
+
+/// A thread holding a lock.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct LockHolder {
+    /// Whether the lock is held for writing.
+    pub write: bool,
+    /// How many times the thread holds the lock.
+    pub count: u64,
+    /// Helgrind thread number, as shown in error messages(`thread #<n>`).
+    pub thread: u64,
+}
+
+/// A lock, as reported by
+/// [`helgrind::monitor::InfoLocksCmd`](crate::helgrind::monitor::InfoLocksCmd).
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub struct LockInfo {
+    /// Guest address of the lock.
+    pub addr: usize,
+    /// Lock kind.
+    pub kind: LockKind,
+    /// Threads holding the lock, empty if it's free.
+    pub holders: Vec<LockHolder>,
+}
+
+impl LockInfo {
+    /// Parses all the locks found in `text`.
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        let mut locks = Vec::new();
+        let mut lines = text.lines().map(str::trim);
+
+        while let Some(line) = lines.next() {
+            let Some(addr) = line.strip_prefix("Lock ga ").and_then(|l| l.strip_suffix(" {"))
+            else {
+                continue;
+            };
+            let Some(addr) = parse_hex(addr) else { continue };
+            let mut lock = Self { addr, kind: LockKind::Other(String::new()), holders: Vec::new() };
+
+            for line in lines.by_ref().take_while(|&line| line != "}") {
+                if let Some(kind) = line.strip_prefix("kind") {
+                    lock.kind = match kind.trim() {
+                        "nonRec" => LockKind::NonRecursive,
+                        "mbRec" => LockKind::Recursive,
+                        "rdwr" => LockKind::ReadWrite,
+                        other => LockKind::Other(other.into()),
+                    };
+                } else if let Some(holders) = line.strip_prefix('{') {
+                    lock.holders.extend(parse_holders(holders));
+                }
+            }
+
+            locks.push(lock);
+        }
+
+        locks
+    }
+}
+
+// `{ W1:thread #2 tid 1 R1:thread #3 tid (exited) }`
+fn parse_holders(text: &str) -> Vec<LockHolder> {
+    let mut holders = Vec::new();
+    let mut words = text.split_whitespace();
+
+    while let Some(word) = words.next() {
+        let Some(count) = word.strip_suffix(":thread") else { continue };
+        let write = match count.as_bytes().first() {
+            Some(b'W') => true,
+            Some(b'R') => false,
+            _ => continue,
+        };
+        let count = number(&count[1..]);
+        let thread = words.next().and_then(|w| w.strip_prefix('#')).and_then(number);
+
+        if let (Some(count), Some(thread)) = (count, thread) {
+            holders.push(LockHolder { write, count, thread });
+        }
+    }
+
+    holders
+}
+
+fn parse_hex(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+impl From<io::Error> for CaptureError {
+    #[inline(always)]
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<MonitorError> for CaptureError {
+    #[inline(always)]
+    fn from(err: MonitorError) -> Self {
+        Self::Monitor(err)
+    }
+}
+
+impl core::fmt::Display for CaptureError {
+    #[inline(always)]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotCaptured => write!(f, "Valgrind log is not captured"),
+            Self::Monitor(err) => write!(f, "{err}"),
+            Self::Io(err) => write!(f, "Valgrind log read failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
@@ -0,0 +1,171 @@
+#![doc = include_str!("../../../doc/valgrind/monitor.md")]
+use super::MonitorError;
+use crate::requests::command::{check_tool, send, token};
+
+use core::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+};
+
+/// A typed monitor command.
+///
+/// The [`Display`] implementation renders the command line, which is formatted into a stack buffer
+/// and executed by [`run`](Command::run).
+pub trait Command: Display {
+    /// Name of the tool which understands the command, [`None`](Option::None) for the core
+    /// commands.
+    const TOOL: Option<&'static str> = None;
+
+    /// Executes the command with [`monitor_command`](super::monitor_command).
+    ///
+    /// Tool-specific commands are sent only if [`toolname`](super::toolname) reports the expected
+    /// tool. With Valgrind older than **3.27**, which can't report the tool name, the command is
+    /// sent anyway and an unknown one fails with [`MonitorError::NotFound`].
+    ///
+    /// # Errors
+    ///
+    /// - [`MonitorError::NotFound`] - The command was not recognized by the running tool.
+    /// - [`MonitorError::WrongTool`] - The command belongs to another tool than the running one.
+    /// - [`MonitorError::InvalidCommand`] - The command is too long, or some argument is
+    ///   malformed.
+    #[inline(always)]
+    fn run(&self) -> Result<(), MonitorError> {
+        if let Some(tool) = Self::TOOL {
+            check_tool(tool)?;
+        }
+
+        send(format_args!("{self}"))
+    }
+}
+
+/// Destination of the monitor commands output. See [`set_output`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum OutputMode {
+    /// Output goes to the connected GDB(`v.set gdb_output`).
+    Gdb,
+    /// Output goes to the Valgrind log(`v.set log_output`).
+    Log,
+    /// Valgrind messages go to the log, the command output goes to GDB(`v.set mixed_output`).
+    Mixed,
+}
+
+/// `xtmemory` command builder.
+///
+/// Produces an "xtree" heap memory report, requires `--xtree-memory=allocs` or
+/// `--xtree-memory=full`. Understood by the tools replacing `malloc`, e.g. Memcheck, Helgrind and
+/// Massif, and re-exported by their `monitor` modules.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct XtMemoryCmd<'a> {
+    path: Option<&'a str>,
+}
+
+impl<'a> XtMemoryCmd<'a> {
+    /// Report written to `path`, or to `xtmemory.kcg.%p.%n` if [`None`](Option::None) is given.
+    ///
+    /// # Errors
+    ///
+    /// - [`MonitorError::InvalidCommand`] - The `path` is empty or contains whitespaces.
+    #[inline]
+    pub fn new(path: impl Into<Option<&'a str>>) -> Result<Self, MonitorError> {
+        let path = path.into();
+        if let Some(path) = path {
+            token(path)?;
+        }
+        Ok(Self { path })
+    }
+}
+
+impl Display for XtMemoryCmd<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.path {
+            Some(path) => write!(f, "xtmemory {path}"),
+            None => f.write_str("xtmemory"),
+        }
+    }
+}
+
+// Shared by several tools, an unsupported one fails with `MonitorError::NotFound`
+impl Command for XtMemoryCmd<'_> {}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/set_output.md")]
+#[inline(always)]
+pub fn set_output(mode: OutputMode) -> Result<(), MonitorError> {
+    let mode = match mode {
+        OutputMode::Gdb => "gdb_output",
+        OutputMode::Log => "log_output",
+        OutputMode::Mixed => "mixed_output",
+    };
+
+    send(format_args!("v.set {mode}"))
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/set_vgdb_error.md")]
+#[inline(always)]
+pub fn set_vgdb_error(errors: usize) -> Result<(), MonitorError> {
+    send(format_args!("v.set vgdb-error {errors}"))
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/all_errors.md")]
+#[inline(always)]
+pub fn all_errors(also_suppressed: bool) -> Result<(), MonitorError> {
+    if also_suppressed {
+        send(format_args!("v.info all_errors also_suppressed"))
+    } else {
+        send(format_args!("v.info all_errors"))
+    }
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/n_errs_found.md")]
+#[inline(always)]
+pub fn n_errs_found<'a>(msg: impl Into<Option<&'a str>>) -> Result<(), MonitorError> {
+    match msg.into() {
+        Some(msg) => send(format_args!("v.info n_errs_found {msg}")),
+        None => send(format_args!("v.info n_errs_found")),
+    }
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/scheduler.md")]
+#[inline(always)]
+pub fn scheduler() -> Result<(), MonitorError> {
+    send(format_args!("v.info scheduler"))
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/stats.md")]
+#[inline(always)]
+pub fn stats() -> Result<(), MonitorError> {
+    send(format_args!("v.info stats"))
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/memory.md")]
+#[inline(always)]
+pub fn memory(aspacemgr: bool) -> Result<(), MonitorError> {
+    if aspacemgr {
+        send(format_args!("v.info memory aspacemgr"))
+    } else {
+        send(format_args!("v.info memory"))
+    }
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/translate.md")]
+#[inline(always)]
+pub fn translate(
+    addr: *const c_void,
+    traceflags: impl Into<Option<u8>>,
+) -> Result<(), MonitorError> {
+    match traceflags.into() {
+        Some(flags) => send(format_args!("v.translate {addr:p} 0b{flags:08b}")),
+        None => send(format_args!("v.translate {addr:p}")),
+    }
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/kill.md")]
+#[inline(always)]
+pub fn kill() -> Result<(), MonitorError> {
+    send(format_args!("v.kill"))
+}
+
+#[doc = include_str!("../../../doc/valgrind/monitor/expensive_sanity_check.md")]
+#[inline(always)]
+pub fn expensive_sanity_check() -> Result<(), MonitorError> {
+    send(format_args!("v.do expensive_sanity_check_general"))
+}
@@ -0,0 +1,237 @@
+use super::{RawFd, capture::LOG_FILE_ENV, running_mode};
+
+use std::{
+    env,
+    ffi::OsString,
+    format, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    string::String,
+    vec::Vec,
+};
+
+/// Environment variable set for the process started by [`reexec`], which prevents it from
+/// re-executing itself again.
+pub const REEXEC_ENV: &str = "CRABGRIND_REEXEC";
+
+/// Valgrind log destination. See [`ToolConfig::log`]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Hash)]
+pub enum LogDestination {
+    /// Standard error, Valgrind default.
+    #[default]
+    Stderr,
+    /// File descriptor (`--log-fd`).
+    Fd(RawFd),
+    /// File (`--log-file`), `%p` in the path is replaced with the process id.
+    File(PathBuf),
+    /// Listening `host[:port]` (`--log-socket`), see `valgrind-listener`.
+    Socket(String),
+    /// Temporary file, readable from within the process with [`capture`](super::capture).
+    ///
+    /// The file is copied to `stderr` and removed once the process exits.
+    Captured,
+}
+
+/// Valgrind command line for [`reexec`].
+///
+/// # Example
+///
+/// ```rust
+/// use crabgrind::valgrind::{LogDestination, ToolConfig};
+///
+/// let config = ToolConfig::new("memcheck")
+///     .suppressions("ci/memcheck.supp")
+///     .log(LogDestination::File("memcheck.%p.log".into()))
+///     .flags(["--leak-check=full", "--error-exitcode=1"]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ToolConfig {
+    tool: String,
+    suppressions: Vec<PathBuf>,
+    log: LogDestination,
+    flags: Vec<OsString>,
+    args: Option<Vec<OsString>>,
+}
+
+impl ToolConfig {
+    /// Configuration running the `tool`(`--tool`), e.g. `"memcheck"`.
+    pub fn new(tool: impl Into<String>) -> Self {
+        Self {
+            tool: tool.into(),
+            suppressions: Vec::new(),
+            log: LogDestination::default(),
+            flags: Vec::new(),
+            args: None,
+        }
+    }
+
+    /// Adds the suppressions file (`--suppressions`), may be called multiple times.
+    #[must_use]
+    pub fn suppressions(mut self, path: impl Into<PathBuf>) -> Self {
+        self.suppressions.push(path.into());
+        self
+    }
+
+    /// Sets the log destination, `stderr` by default.
+    #[must_use]
+    pub fn log(mut self, log: LogDestination) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Adds the core or tool flag, passed to Valgrind as is, e.g. `--leak-check=full`.
+    #[must_use]
+    pub fn flag(mut self, flag: impl Into<OsString>) -> Self {
+        self.flags.push(flag.into());
+        self
+    }
+
+    /// Adds the core or tool flags, passed to Valgrind as is.
+    #[must_use]
+    pub fn flags<I>(mut self, flags: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        self.flags.extend(flags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Replaces the arguments of the re-executed program, the current ones by default.
+    #[must_use]
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        self.args = Some(args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Builds the command running the current executable, with the same arguments (unless
+    /// replaced with [`args`](ToolConfig::args)) and environment, under Valgrind.
+    ///
+    /// # Errors
+    ///
+    /// - [`io::Error`] - The current executable path is unknown, or the log destination is
+    ///   [`LogDestination::Captured`], whose temporary file only [`reexec`] sets up.
+    pub fn command(&self) -> io::Result<process::Command> {
+        if self.log == LogDestination::Captured {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the captured log is only available through `reexec`",
+            ));
+        }
+        self.build(None)
+    }
+
+    // Builds the command, `captured` being the temporary log file of `LogDestination::Captured`.
+    fn build(&self, captured: Option<&Path>) -> io::Result<process::Command> {
+        let mut cmd = process::Command::new("valgrind");
+
+        cmd.arg(format!("--tool={}", self.tool));
+        for path in &self.suppressions {
+            cmd.arg(path_arg("--suppressions=", path));
+        }
+
+        match (&self.log, captured) {
+            (LogDestination::Fd(fd), _) => {
+                cmd.arg(format!("--log-fd={fd}"));
+            }
+            (LogDestination::File(path), _) => {
+                cmd.arg(path_arg("--log-file=", path));
+            }
+            (LogDestination::Socket(addr), _) => {
+                cmd.arg(format!("--log-socket={addr}"));
+            }
+            (LogDestination::Captured, Some(path)) => {
+                cmd.arg(path_arg("--log-file=", path)).env(LOG_FILE_ENV, path);
+            }
+            (LogDestination::Stderr | LogDestination::Captured, _) => {}
+        }
+
+        cmd.args(&self.flags).arg(env::current_exe()?);
+        match &self.args {
+            Some(args) => cmd.args(args),
+            None => cmd.args(env::args_os().skip(1)),
+        };
+        cmd.env(REEXEC_ENV, "");
+
+        Ok(cmd)
+    }
+}
+
+// `--flag=path` argument, keeping non UTF-8 paths intact.
+fn path_arg(flag: &str, path: &Path) -> OsString {
+    let mut arg = OsString::from(flag);
+    arg.push(path);
+    arg
+}
+
+// Temporary file, removed when dropped.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[doc = include_str!("../../../doc/valgrind/reexec.md")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn reexec(config: ToolConfig) -> io::Result<()> {
+    // the configuration is checked even if the program is not re-executed
+    let cmd = config.build(None)?;
+    if !cfg!(feature = "valgrind")
+        || !running_mode().is_native()
+        || env::var_os(REEXEC_ENV).is_some()
+    {
+        return Ok(());
+    }
+
+    if config.log != LogDestination::Captured {
+        return exec(cmd);
+    }
+
+    let log = TempFile(temp_file("crabgrind.log")?);
+    let status = config.build(Some(&log.0))?.status()?;
+
+    // forward the log, as if Valgrind wrote it to stderr
+    if let Ok(text) = fs::read(&log.0) {
+        let _ = io::stderr().write_all(&text);
+    }
+    drop(log);
+
+    process::exit(status.code().unwrap_or(1))
+}
+
+// Creates an empty file in the temporary directory, named after `prefix` and the process id.
+//
+// The file is created exclusively, so a file or symlink planted at a predictable path by another
+// user is never written through.
+pub(crate) fn temp_file(prefix: &str) -> io::Result<PathBuf> {
+    let dir = env::temp_dir();
+    let mut n = 0u32;
+    loop {
+        let path = dir.join(format!("{prefix}.{}.{n}", process::id()));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && n < 1000 => n += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn exec(mut cmd: process::Command) -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // returns only on failure
+    Err(cmd.exec())
+}
+
+#[cfg(not(unix))]
+fn exec(mut cmd: process::Command) -> io::Result<()> {
+    process::exit(cmd.status()?.code().unwrap_or(1))
+}
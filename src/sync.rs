@@ -0,0 +1,271 @@
+#![doc = include_str!("../doc/sync.md")]
+use crate::helgrind::{
+    annotate_rwlock_acquired, annotate_rwlock_create, annotate_rwlock_destroy,
+    annotate_rwlock_released,
+};
+
+use core::{
+    ffi::c_void,
+    hint::spin_loop,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+mod atomic;
+mod rc;
+
+pub use atomic::*;
+#[cfg(feature = "std")]
+pub use rc::HgArc;
+pub use rc::RefCount;
+
+/// Lock without data, e.g. a spinlock or a futex mutex.
+///
+/// [`unlock`](RawLock::unlock) must be called only by the holder of the lock.
+pub trait RawLock {
+    /// Acquires the lock, blocking until it's available.
+    fn lock(&self);
+
+    /// Acquires the lock if it's available, returns whether it was acquired.
+    fn try_lock(&self) -> bool;
+
+    /// Releases the lock.
+    fn unlock(&self);
+}
+
+/// Reader-writer lock without data, the [`RawLock`] methods acquire it in exclusive (write) mode.
+///
+/// [`unlock_shared`](RawRwLock::unlock_shared) must be called only by a holder of the lock.
+pub trait RawRwLock: RawLock {
+    /// Acquires the lock in shared (read) mode, blocking until it's available.
+    fn lock_shared(&self);
+
+    /// Acquires the lock in shared mode if it's available, returns whether it was acquired.
+    fn try_lock_shared(&self) -> bool;
+
+    /// Releases the lock acquired in shared mode.
+    fn unlock_shared(&self);
+}
+
+/// Test-and-test-and-set spinlock, a reference [`RawLock`].
+///
+/// Not understood by Helgrind and DRD on its own, see [`Annotated`].
+#[derive(Debug, Default)]
+pub struct SpinMutex {
+    locked: AtomicBool,
+}
+
+impl SpinMutex {
+    /// Creates an unlocked spinlock.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { locked: AtomicBool::new(false) }
+    }
+
+    /// Whether the lock is held by some thread.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+}
+
+impl RawLock for SpinMutex {
+    #[inline]
+    fn lock(&self) {
+        while !self.try_lock() {
+            while self.is_locked() {
+                spin_loop();
+            }
+        }
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.locked.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok()
+    }
+
+    #[inline]
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+}
+
+/// Reader-writer spinlock, a reference [`RawRwLock`].
+///
+/// Readers don't wait for the waiting writers, so a steady stream of readers may starve them.
+/// Not understood by Helgrind and DRD on its own, see [`Annotated`].
+#[derive(Debug, Default)]
+pub struct RwSpinLock {
+    // `WRITER` bit, and the number of readers in the other bits
+    state: AtomicUsize,
+}
+
+const WRITER: usize = 1;
+const READER: usize = 2;
+
+impl RwSpinLock {
+    /// Creates an unlocked lock.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { state: AtomicUsize::new(0) }
+    }
+
+    /// Whether the lock is held in exclusive mode.
+    #[inline]
+    pub fn is_locked_exclusive(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & WRITER != 0
+    }
+
+    /// Number of the shared holders.
+    #[inline]
+    pub fn readers(&self) -> usize {
+        self.state.load(Ordering::Relaxed) / READER
+    }
+}
+
+impl RawLock for RwSpinLock {
+    #[inline]
+    fn lock(&self) {
+        while !self.try_lock() {
+            while self.state.load(Ordering::Relaxed) != 0 {
+                spin_loop();
+            }
+        }
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.state.compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed).is_ok()
+    }
+
+    #[inline]
+    fn unlock(&self) {
+        self.state.fetch_and(!WRITER, Ordering::Release);
+    }
+}
+
+impl RawRwLock for RwSpinLock {
+    #[inline]
+    fn lock_shared(&self) {
+        while !self.try_lock_shared() {
+            while self.is_locked_exclusive() {
+                spin_loop();
+            }
+        }
+    }
+
+    #[inline]
+    fn try_lock_shared(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+        state & WRITER == 0
+            && self
+                .state
+                .compare_exchange_weak(state, state + READER, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+    }
+
+    #[inline]
+    fn unlock_shared(&self) {
+        self.state.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+#[doc = include_str!("../doc/sync/Annotated.md")]
+#[derive(Debug, Default)]
+pub struct Annotated<L> {
+    lock: L,
+    // Address the lock was announced at, null before the first use
+    created: AtomicPtr<c_void>,
+}
+
+impl<L> Annotated<L> {
+    /// Wraps the lock.
+    #[inline]
+    pub const fn new(lock: L) -> Self {
+        Self { lock, created: AtomicPtr::new(ptr::null_mut()) }
+    }
+
+    /// The wrapped lock.
+    #[inline]
+    pub fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    // Announces the lock at its current address, on the first use and after a move.
+    #[inline(always)]
+    fn create(&self) -> *const c_void {
+        let addr = (self as *const Self).cast_mut().cast::<c_void>();
+
+        if self.created.load(Ordering::Relaxed) != addr {
+            let prev = self.created.swap(addr, Ordering::Relaxed);
+            if prev != addr {
+                if !prev.is_null() {
+                    annotate_rwlock_destroy(prev);
+                }
+                annotate_rwlock_create(addr);
+            }
+        }
+
+        addr
+    }
+}
+
+impl<L: RawLock> RawLock for Annotated<L> {
+    #[inline]
+    fn lock(&self) {
+        let addr = self.create();
+        self.lock.lock();
+        annotate_rwlock_acquired(addr, true);
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        let addr = self.create();
+        let locked = self.lock.try_lock();
+        if locked {
+            annotate_rwlock_acquired(addr, true);
+        }
+        locked
+    }
+
+    #[inline]
+    fn unlock(&self) {
+        annotate_rwlock_released(self.create(), true);
+        self.lock.unlock();
+    }
+}
+
+impl<L: RawRwLock> RawRwLock for Annotated<L> {
+    #[inline]
+    fn lock_shared(&self) {
+        let addr = self.create();
+        self.lock.lock_shared();
+        annotate_rwlock_acquired(addr, false);
+    }
+
+    #[inline]
+    fn try_lock_shared(&self) -> bool {
+        let addr = self.create();
+        let locked = self.lock.try_lock_shared();
+        if locked {
+            annotate_rwlock_acquired(addr, false);
+        }
+        locked
+    }
+
+    #[inline]
+    fn unlock_shared(&self) {
+        annotate_rwlock_released(self.create(), false);
+        self.lock.unlock_shared();
+    }
+}
+
+impl<L> Drop for Annotated<L> {
+    #[inline]
+    fn drop(&mut self) {
+        let created = *self.created.get_mut();
+        if !created.is_null() {
+            annotate_rwlock_destroy(created);
+        }
+    }
+}
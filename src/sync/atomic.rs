@@ -0,0 +1,222 @@
+use crate::helgrind::{
+    annotate_happens_after, annotate_happens_before, annotate_happens_before_forget_all,
+};
+
+use core::{ffi::c_void, sync::atomic::Ordering};
+
+// `annotate_happens_before` ahead of a store or RMW with a release ordering
+#[inline(always)]
+fn release(addr: *const c_void, order: Ordering) {
+    if matches!(order, Ordering::Release | Ordering::AcqRel | Ordering::SeqCst) {
+        annotate_happens_before(addr);
+    }
+}
+
+// `annotate_happens_after` after a load or RMW with an acquire ordering
+#[inline(always)]
+fn acquire(addr: *const c_void, order: Ordering) {
+    if matches!(order, Ordering::Acquire | Ordering::AcqRel | Ordering::SeqCst) {
+        annotate_happens_after(addr);
+    }
+}
+
+// Methods shared by all the annotated atomics.
+macro_rules! annotated_atomic {
+    ($name:ident $(<$t:ident>)?, $atomic:ty, $value:ty) => {
+        impl$(<$t>)? $name$(<$t>)? {
+            /// Creates a new atomic.
+            #[inline]
+            pub const fn new(value: $value) -> Self {
+                Self { inner: <$atomic>::new(value) }
+            }
+
+            /// The wrapped atomic, its operations are not annotated.
+            #[inline]
+            pub fn as_inner(&self) -> &$atomic {
+                &self.inner
+            }
+
+            /// Mutable reference to the value, see the wrapped atomic's `get_mut`.
+            #[inline]
+            pub fn get_mut(&mut self) -> &mut $value {
+                self.inner.get_mut()
+            }
+
+            /// Consumes the atomic and returns the value.
+            #[inline]
+            pub fn into_inner(mut self) -> $value {
+                *self.inner.get_mut()
+            }
+
+            #[inline(always)]
+            fn addr(&self) -> *const c_void {
+                (self as *const Self).cast()
+            }
+
+            /// Loads the value, `annotate_happens_after` with an acquire `order`.
+            #[inline]
+            pub fn load(&self, order: Ordering) -> $value {
+                let value = self.inner.load(order);
+                acquire(self.addr(), order);
+                value
+            }
+
+            /// Stores the value, `annotate_happens_before` with a release `order`.
+            #[inline]
+            pub fn store(&self, value: $value, order: Ordering) {
+                release(self.addr(), order);
+                self.inner.store(value, order);
+            }
+
+            /// Swaps the value, annotated as per `order`.
+            #[inline]
+            pub fn swap(&self, value: $value, order: Ordering) -> $value {
+                release(self.addr(), order);
+                let prev = self.inner.swap(value, order);
+                acquire(self.addr(), order);
+                prev
+            }
+
+            /// Stores `new` if the value is `current`, annotated as per `success` or `failure`.
+            ///
+            /// A release `success` is annotated even if the exchange fails, see the
+            /// [module](crate::sync) docs.
+            ///
+            /// # Errors
+            ///
+            /// The current value, if it's not `current`.
+            #[inline]
+            pub fn compare_exchange(
+                &self,
+                current: $value,
+                new: $value,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$value, $value> {
+                release(self.addr(), success);
+                let result = self.inner.compare_exchange(current, new, success, failure);
+                acquire(self.addr(), if result.is_ok() { success } else { failure });
+                result
+            }
+
+            /// Same as [`compare_exchange`](Self::compare_exchange), but may fail spuriously.
+            ///
+            /// # Errors
+            ///
+            /// The current value, if it's not `current` or on a spurious failure.
+            #[inline]
+            pub fn compare_exchange_weak(
+                &self,
+                current: $value,
+                new: $value,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$value, $value> {
+                release(self.addr(), success);
+                let result = self.inner.compare_exchange_weak(current, new, success, failure);
+                acquire(self.addr(), if result.is_ok() { success } else { failure });
+                result
+            }
+        }
+
+        impl$(<$t>)? Drop for $name$(<$t>)? {
+            #[inline]
+            fn drop(&mut self) {
+                annotate_happens_before_forget_all(self.addr());
+            }
+        }
+    };
+}
+
+// Read-modify-write operations, annotated as per their ordering.
+macro_rules! fetch_ops {
+    ($name:ident, $value:ty, $($op:ident),+) => {
+        impl $name {
+            $(
+                #[doc = concat!(
+                    "See the wrapped atomic's `", stringify!($op), "`, annotated as per `order`."
+                )]
+                #[inline]
+                pub fn $op(&self, value: $value, order: Ordering) -> $value {
+                    release(self.addr(), order);
+                    let prev = self.inner.$op(value, order);
+                    acquire(self.addr(), order);
+                    prev
+                }
+            )+
+        }
+    };
+}
+
+macro_rules! annotated_int {
+    ($(#[$cfg:meta])* $name:ident, $atomic:ident, $int:ty) => {
+        $(#[$cfg])*
+        #[doc = concat!(
+            "[`", stringify!($atomic), "`](core::sync::atomic::", stringify!($atomic),
+            ") annotated for Helgrind, see the [module](crate::sync) docs."
+        )]
+        #[derive(Debug, Default)]
+        #[repr(transparent)]
+        pub struct $name {
+            inner: core::sync::atomic::$atomic,
+        }
+
+        $(#[$cfg])*
+        annotated_atomic!($name, core::sync::atomic::$atomic, $int);
+        $(#[$cfg])*
+        fetch_ops!(
+            $name, $int, fetch_add, fetch_sub, fetch_and, fetch_or, fetch_xor, fetch_max, fetch_min
+        );
+    };
+}
+
+annotated_int!(AnnotatedAtomicUsize, AtomicUsize, usize);
+annotated_int!(AnnotatedAtomicIsize, AtomicIsize, isize);
+annotated_int!(
+    #[cfg(target_has_atomic = "32")]
+    AnnotatedAtomicU32,
+    AtomicU32,
+    u32
+);
+annotated_int!(
+    #[cfg(target_has_atomic = "32")]
+    AnnotatedAtomicI32,
+    AtomicI32,
+    i32
+);
+annotated_int!(
+    #[cfg(target_has_atomic = "64")]
+    AnnotatedAtomicU64,
+    AtomicU64,
+    u64
+);
+annotated_int!(
+    #[cfg(target_has_atomic = "64")]
+    AnnotatedAtomicI64,
+    AtomicI64,
+    i64
+);
+
+/// [`AtomicBool`](core::sync::atomic::AtomicBool) annotated for Helgrind, see the
+/// [module](crate::sync) docs.
+#[cfg(target_has_atomic = "8")]
+#[derive(Debug, Default)]
+#[repr(transparent)]
+pub struct AnnotatedAtomicBool {
+    inner: core::sync::atomic::AtomicBool,
+}
+
+#[cfg(target_has_atomic = "8")]
+annotated_atomic!(AnnotatedAtomicBool, core::sync::atomic::AtomicBool, bool);
+#[cfg(target_has_atomic = "8")]
+fetch_ops!(AnnotatedAtomicBool, bool, fetch_and, fetch_or, fetch_xor);
+
+/// [`AtomicPtr`](core::sync::atomic::AtomicPtr) annotated for Helgrind, see the
+/// [module](crate::sync) docs.
+#[derive(Debug, Default)]
+#[repr(transparent)]
+pub struct AnnotatedAtomicPtr<T> {
+    inner: core::sync::atomic::AtomicPtr<T>,
+}
+
+annotated_atomic!(AnnotatedAtomicPtr<T>, core::sync::atomic::AtomicPtr<T>, *mut T);
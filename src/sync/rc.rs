@@ -0,0 +1,222 @@
+use crate::helgrind::{
+    annotate_happens_after, annotate_happens_before, annotate_happens_before_forget_all,
+};
+
+use core::{
+    ffi::c_void,
+    sync::atomic::{AtomicUsize, Ordering, fence},
+};
+#[cfg(feature = "std")]
+use core::{fmt, marker::PhantomData, mem::ManuallyDrop, ops::Deref, ptr::NonNull};
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+// Same limit as `Arc`, so a leaked clone loop can't wrap the count around
+const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+#[doc = include_str!("../../doc/sync/RefCount.md")]
+#[derive(Debug)]
+pub struct RefCount {
+    count: AtomicUsize,
+}
+
+impl RefCount {
+    /// Creates a count of `count` references.
+    #[inline]
+    pub const fn new(count: usize) -> Self {
+        Self { count: AtomicUsize::new(count) }
+    }
+
+    /// Current number of references, a snapshot that may be stale right away.
+    #[inline]
+    pub fn get(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Adds a reference, when cloning.
+    ///
+    /// # Panics
+    ///
+    /// If the count exceeds `isize::MAX`.
+    #[inline]
+    pub fn increment(&self) {
+        let prev = self.count.fetch_add(1, Ordering::Relaxed);
+        assert!(prev < MAX_REFCOUNT, "reference count overflow");
+    }
+
+    /// Drops a reference, returns whether it was the last one and the shared value must be
+    /// dropped now.
+    #[inline]
+    pub fn decrement(&self) -> bool {
+        annotate_happens_before(self.addr());
+        if self.count.fetch_sub(1, Ordering::Release) != 1 {
+            return false;
+        }
+        fence(Ordering::Acquire);
+        annotate_happens_after(self.addr());
+        true
+    }
+
+    /// Whether this is the only reference, e.g. before handing out a mutable reference.
+    #[inline]
+    pub fn is_unique(&self) -> bool {
+        let unique = self.count.load(Ordering::Acquire) == 1;
+        if unique {
+            annotate_happens_after(self.addr());
+        }
+        unique
+    }
+
+    #[inline(always)]
+    fn addr(&self) -> *const c_void {
+        (self as *const Self).cast()
+    }
+}
+
+impl Drop for RefCount {
+    #[inline]
+    fn drop(&mut self) {
+        annotate_happens_before_forget_all(self.addr());
+    }
+}
+
+#[doc = include_str!("../../doc/sync/HgArc.md")]
+#[cfg(feature = "std")]
+pub struct HgArc<T> {
+    ptr: NonNull<Inner<T>>,
+    _owns: PhantomData<Inner<T>>,
+}
+
+#[cfg(feature = "std")]
+struct Inner<T> {
+    count: RefCount,
+    value: T,
+}
+
+#[cfg(feature = "std")]
+unsafe impl<T: Send + Sync> Send for HgArc<T> {}
+#[cfg(feature = "std")]
+unsafe impl<T: Send + Sync> Sync for HgArc<T> {}
+
+#[cfg(feature = "std")]
+impl<T> HgArc<T> {
+    /// Moves the value to the heap, with a single reference.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        let inner = Box::new(Inner { count: RefCount::new(1), value });
+        Self { ptr: NonNull::from(Box::leak(inner)), _owns: PhantomData }
+    }
+
+    /// Number of references to the value.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().count.get()
+    }
+
+    /// Whether both point to the same value.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    /// Mutable reference to the value, if there are no other references.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if this.inner().count.is_unique() {
+            // SAFETY: the only reference, borrowed mutably
+            Some(unsafe { &mut (*this.ptr.as_ptr()).value })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value, if there are no other references.
+    ///
+    /// # Errors
+    ///
+    /// The same `HgArc`, if there are other references.
+    #[inline]
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if !this.inner().count.is_unique() {
+            return Err(this);
+        }
+        let this = ManuallyDrop::new(this);
+        // SAFETY: the only reference, nobody else can clone it anymore
+        let inner = unsafe { Box::from_raw(this.ptr.as_ptr()) };
+        Ok(inner.value)
+    }
+
+    #[inline(always)]
+    fn inner(&self) -> &Inner<T> {
+        // SAFETY: alive while there are references
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Clone for HgArc<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.inner().count.increment();
+        Self { ptr: self.ptr, _owns: PhantomData }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Deref for HgArc<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.inner().value
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for HgArc<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.inner().count.decrement() {
+            // SAFETY: the last reference
+            drop(unsafe { Box::from_raw(self.ptr.as_ptr()) });
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> AsRef<T> for HgArc<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> From<T> for HgArc<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Default> Default for HgArc<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> fmt::Debug for HgArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Display> fmt::Display for HgArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
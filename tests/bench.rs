@@ -0,0 +1,96 @@
+#![cfg(feature = "std")]
+use crabgrind::bench::*;
+
+mod common;
+
+#[test]
+fn costs_baseline_format() {
+    let costs: Costs = "Ir=1200 Dr=300 Dw=150 D1mr=7".parse().unwrap();
+
+    assert_eq!(costs[Event::Ir], 1200);
+    assert_eq!(costs[Event::D1mr], 7);
+    assert_eq!(costs[Event::DLmw], 0);
+    assert_eq!(costs.l1_misses(), 7);
+    assert_eq!(costs.to_string().parse::<Costs>(), Ok(costs));
+    assert!("Ir=1 Xx=2".parse::<Costs>().is_err());
+}
+
+#[cfg(feature = "valgrind")]
+mod valgrind {
+    use crabgrind::bench::*;
+    use std::hint::black_box;
+
+    use super::common::*;
+
+    fn factorial(num: u128) -> u128 {
+        (1..=black_box(num)).product()
+    }
+
+    // Runs the test in a separate process, so that the harness re-executes only this one.
+    fn isolated(test_name: &str, test_fn: impl FnOnce()) {
+        if std::env::var(TEST_RUNNER).is_ok() {
+            return test_fn();
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([test_name, "--exact", "--no-capture"])
+            .env(TEST_RUNNER, "")
+            .output()
+            .unwrap();
+
+        if !output.status.success() {
+            inline_panic(output.stdout, output.stderr);
+        }
+    }
+
+    #[test]
+    fn callgrind() {
+        isolated("valgrind::callgrind", || {
+            let report = Bench::new(Tool::Callgrind)
+                .bench("small", || factorial(5))
+                .bench_with("large", || vec![0u8; 4096], |v| factorial(30) + v.len() as u128)
+                .run()
+                .unwrap();
+
+            let [small, large] = &report.results[..] else { panic!("{report:?}") };
+            assert!(small.costs[Event::Ir] > 0);
+            assert!(large.costs[Event::Ir] > small.costs[Event::Ir]);
+            assert!(large.costs[Event::Dr] > 0);
+        });
+    }
+
+    #[test]
+    fn cachegrind() {
+        isolated("valgrind::cachegrind", || {
+            let report =
+                Bench::new(Tool::Cachegrind).bench("small", || factorial(5)).run().unwrap();
+            assert!(report.results[0].costs[Event::Ir] > 0);
+        });
+    }
+
+    #[test]
+    fn baseline_regression() {
+        isolated("valgrind::baseline_regression", || {
+            let baseline = std::env::temp_dir().join("crabgrind.bench.baseline");
+            std::fs::write(&baseline, "work Ir=1\n").unwrap();
+
+            let result = Bench::new(Tool::Callgrind)
+                .bench("work", || factorial(20))
+                .baseline(&baseline)
+                .threshold(5.0)
+                .run();
+            let _ = std::fs::remove_file(baseline);
+
+            let Err(BenchError::Regression(regressions)) = result else { panic!("{result:?}") };
+            assert_eq!(regressions[0].name, "work");
+            assert_eq!(regressions[0].event, Event::Ir);
+            assert_eq!(regressions[0].baseline, 1);
+        });
+    }
+
+    #[test]
+    fn invalid_name() {
+        let result = Bench::new(Tool::Callgrind).bench("two words", || ()).run();
+        assert!(matches!(result, Err(BenchError::InvalidName(_))));
+    }
+}
@@ -28,3 +28,41 @@ fn stop_instrumentation() {
         assert!(stderr.contains("warning: CACHEGRIND_STOP_INSTRUMENTATION called"));
     });
 }
+
+#[test]
+fn instrument() {
+    valgrind!(cachegrind --verbose --instr-atstart=no => {
+        let _outer = cg::instrument();
+        {
+            // no-op, instrumentation is already running
+            let _inner = cg::instrument();
+        }
+        let _nested = cg::instrument();
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(!stderr.contains("warning: CACHEGRIND_START_INSTRUMENTATION called"));
+        assert!(!stderr.contains("warning: CACHEGRIND_STOP_INSTRUMENTATION called"));
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn format_read() {
+    use crabgrind::cachegrind::format::Profile;
+
+    valgrind!(cachegrind --cache-sim=yes => {
+        println!("||{}||", std::process::id());
+    }, |output: Output| {
+        let stdout = as_str!(&output.stdout);
+        let pid = stdout.split("||").nth(1).unwrap();
+        let path = format!("cachegrind.out.{pid}");
+
+        let profile = Profile::read(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert!(profile.cmd.is_some());
+        assert_eq!(profile.events[0], "Ir");
+        assert!(profile.summary[0] > 0);
+        assert!(profile.event("D1mr").is_some());
+    });
+}
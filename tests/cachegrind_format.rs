@@ -0,0 +1,108 @@
+#![cfg(feature = "std")]
+use crabgrind::cachegrind::format::*;
+
+const BEFORE: &str = "\
+# cachegrind format
+desc: I1 cache:         32768 B, 64 B, 8-way associative
+desc: D1 cache:         32768 B, 64 B, 8-way associative
+desc: LL cache:         8388608 B, 64 B, 16-way associative
+cmd: ./app --before
+events: Ir I1mr ILmr Dr D1mr DLmr Dw D1mw DLmw
+fl=src/main.rs
+fn=main
+3 10 1 1 4 0 0 2 0 0
+4 5
+fn=lookup
+10 100 0 0 40 8 2 10 1 0
+11 20 0 0 4 2 0
+fl=src/util.rs
+fn=lookup
+2 7 0 0 1 1 1
+summary: 142 1 1 49 11 3 12 1 0
+";
+
+const AFTER: &str = "\
+events: Ir I1mr ILmr Dr D1mr DLmr Dw D1mw DLmw
+fl=src/main.rs
+fn=main
+3 10 1 1 4 0 0 2 0 0
+4 5
+fn=lookup
+10 90 0 0 40 4 1 10 1 0
+fl=src/new.rs
+fn=helper
+1 3
+summary: 108 1 1 44 4 1 12 1 0
+";
+
+#[test]
+fn parse() {
+    let profile = Profile::parse(BEFORE).unwrap();
+
+    assert_eq!(profile.desc.len(), 3);
+    assert_eq!(profile.cmd.as_deref(), Some("./app --before"));
+    assert_eq!(profile.events.len(), 9);
+    assert_eq!(profile.summary, [142, 1, 1, 49, 11, 3, 12, 1, 0]);
+    assert_eq!(profile.functions.len(), 3);
+
+    let main = profile.function("main").unwrap();
+    assert_eq!(main.file, "src/main.rs");
+    assert_eq!(main.costs, [15, 1, 1, 4, 0, 0, 2, 0, 0]);
+    assert_eq!(main.lines[&4], [5, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    assert_eq!(profile.cost("lookup", "Ir"), Some(127));
+    assert_eq!(profile.cost("lookup", "D1mr"), Some(11));
+    assert_eq!(profile.cost("lookup", "Bogus"), None);
+    assert_eq!(profile.cost("missing", "Ir"), Some(0));
+}
+
+#[test]
+fn diff() {
+    let before = Profile::parse(BEFORE).unwrap();
+    let after = Profile::parse(AFTER).unwrap();
+    let diff = before.diff(&after).unwrap();
+
+    assert_eq!(diff.summary, [-34, 0, 0, -5, -7, -2, 0, 0, 0]);
+    // `main` didn't change
+    assert!(diff.functions.iter().all(|function| function.name != "main"));
+
+    assert_eq!(diff.cost("lookup", "Ir"), Some(-37));
+    assert_eq!(diff.cost("lookup", "D1mr"), Some(-7));
+    assert_eq!(diff.cost("lookup", "DLmr"), Some(-2));
+    assert_eq!(diff.cost("helper", "Ir"), Some(3));
+}
+
+#[test]
+fn merge() {
+    let mut merged = Profile::parse(BEFORE).unwrap();
+    merged.merge(&Profile::parse(AFTER).unwrap()).unwrap();
+
+    assert_eq!(merged.cmd.as_deref(), Some("./app --before"));
+    assert_eq!(merged.summary[0], 250);
+    assert_eq!(merged.functions.len(), 4);
+    assert_eq!(merged.cost("main", "Ir"), Some(30));
+    assert_eq!(merged.cost("lookup", "Ir"), Some(217));
+    assert_eq!(merged.function("main").unwrap().lines[&3][0], 20);
+}
+
+#[test]
+fn event_mismatch() {
+    let before = Profile::parse(BEFORE).unwrap();
+    let mut other = Profile::parse("events: Ir\nsummary: 1\n").unwrap();
+
+    assert!(matches!(before.diff(&other), Err(FormatError::EventMismatch)));
+    assert!(matches!(other.merge(&before), Err(FormatError::EventMismatch)));
+}
+
+#[test]
+fn syntax_errors() {
+    let err = |text| match Profile::parse(text) {
+        Err(FormatError::Syntax { line, .. }) => line,
+        other => panic!("{other:?}"),
+    };
+
+    assert_eq!(err("events: Ir\n1 10\n"), 2);
+    assert_eq!(err("events: Ir\nfn=a\n1 x\n"), 3);
+    assert_eq!(err("events: Ir\nob=a\n"), 2);
+    assert_eq!(err("events: Ir\nsummary: a\n"), 2);
+}
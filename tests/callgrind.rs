@@ -24,6 +24,57 @@ fn dump_stats() {
     });
 }
 
+#[test]
+fn dump_stats_macro() {
+    valgrind!(callgrind --verbose => {
+        for i in 0..2 {
+            factorial(10);
+            cg::dump_stats!("iteration {i}");
+        }
+        cg::dump_stats!("{}\0{}", "nul", "x".repeat(cg::REASON_LEN));
+        cg::dump_stats!();
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("(Client Request: iteration 0)..."));
+        assert!(stderr.contains("(Client Request: iteration 1)..."));
+        let truncated = format!("nul{}", "x".repeat(cg::REASON_LEN - 3));
+        assert!(stderr.contains(&format!("(Client Request: {truncated})...")));
+        assert!(stderr.contains("(Client Request)..."));
+    });
+}
+
+#[test]
+fn dumper() {
+    valgrind!(callgrind --verbose => {
+        let mut dumper = cg::Dumper::new("request");
+        dumper.dump();
+        dumper.dump_phase("parse");
+        assert_eq!(dumper.count(), 2);
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("(Client Request: request #1)..."));
+        assert!(stderr.contains("(Client Request: request #2: parse)..."));
+    });
+}
+
+#[cfg(all(feature = "signals", unix))]
+#[test]
+fn signal_handlers() {
+    valgrind!(callgrind --verbose => {
+        cg::install_signal_handlers().unwrap();
+
+        unsafe { libc::raise(libc::SIGUSR2) };
+        factorial(10);
+        unsafe { libc::raise(libc::SIGUSR1) };
+        unsafe { libc::raise(libc::SIGUSR1) };
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("Zeroing costs..."));
+        assert!(stderr.contains("(Client Request: SIGUSR1 #1 at "));
+        assert!(stderr.contains("(Client Request: SIGUSR1 #2 at "));
+    });
+}
+
 #[test]
 fn zero_stats() {
     valgrind!(callgrind --verbose => {
@@ -67,3 +118,114 @@ fn instrumentation() {
         assert!(stderr.contains("Client Request: instrumentation switched ON"));
     });
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn collect() {
+    valgrind!(callgrind --verbose --collect-atstart=no => {
+        let _outer = cg::collect();
+        {
+            let _inner = cg::collect();
+        }
+        // still collecting
+        factorial(10);
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(!stderr.contains("Collected : 0"))
+    });
+}
+
+#[test]
+fn instrument() {
+    valgrind!(callgrind --verbose --instr-atstart=no => {
+        let outer = cg::instrument();
+        drop(cg::instrument());
+        crabgrind::println!("INNER_DROPPED");
+        drop(outer);
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        let (before, after) = stderr.split_once("INNER_DROPPED").unwrap();
+        assert!(before.contains("instrumentation switched ON"));
+        assert!(!before.contains("instrumentation switched OFF"));
+        assert!(after.contains("instrumentation switched OFF"));
+    });
+}
+
+#[crabgrind::profile(dump = "PROFILED", zero = true)]
+fn profiled(num: u128) -> u128 {
+    if num == 0 {
+        return 1;
+    }
+    factorial(num)
+}
+
+#[test]
+fn profile() {
+    valgrind!(callgrind --verbose --collect-atstart=no => {
+        profiled(0);
+        profiled(10);
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert_eq!(stderr.matches("(Client Request: PROFILED)...").count(), 2);
+        assert!(stderr.contains("Zeroing costs..."));
+        assert!(!stderr.contains("Collected : 0"));
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn format_read_dumps() {
+    use crabgrind::callgrind::format::Profile;
+
+    valgrind!(callgrind => {
+        factorial(10);
+        cg::dump_stats(cstr!("FORMAT"));
+
+        let base = format!("callgrind.out.{}", std::process::id());
+        let profile = Profile::read_dumps(&base).unwrap();
+        let part = profile.dump("FORMAT").unwrap();
+
+        let ir = part.event("Ir").unwrap();
+        assert!(part.totals[ir] > 0);
+        assert!(part.functions.iter().any(|f| f.name.contains("factorial") && f.inclusive[ir] > 0));
+
+        for entry in std::fs::read_dir(".").unwrap() {
+            let path = entry.unwrap().path();
+            if path.file_name().unwrap().to_str().unwrap().starts_with(&format!("{base}.")) {
+                std::fs::remove_file(path).unwrap();
+            }
+        }
+    });
+}
+
+#[test]
+fn monitor_commands() {
+    use cg::monitor::{Command, DumpCmd, InstrumentationCmd, StatusCmd, ZeroCmd};
+
+    valgrind!(callgrind --verbose => {
+        ZeroCmd.run().unwrap();
+        factorial(10);
+        DumpCmd::new("MONITOR_DUMP").run().unwrap();
+        StatusCmd.run().unwrap();
+        InstrumentationCmd::off().run().unwrap();
+        InstrumentationCmd::on().run().unwrap();
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("Zeroing costs..."));
+        assert!(stderr.contains("MONITOR_DUMP"));
+        assert!(stderr.contains("instrumentation switched OFF"));
+        assert!(stderr.contains("instrumentation switched ON"));
+    });
+}
+
+#[test]
+fn monitor_wrong_tool() {
+    use cg::monitor::{Command, ZeroCmd};
+    use crabgrind::valgrind::MonitorError;
+
+    valgrind!(memcheck => {
+        if crabgrind::VALGRIND_VERSION >= (3, 27) {
+            assert_eq!(ZeroCmd.run(), Err(MonitorError::WrongTool));
+        }
+    });
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "std")]
+use crabgrind::callgrind::{export, format::Profile};
+
+const PROFILE: &str = "\
+# callgrind format
+version: 1
+cmd: ./app
+part: 1
+
+desc: Trigger: Client Request: first
+positions: line
+events: Ir Bcm
+summary: 575 8
+
+fl=(1) main.rs
+fn=(1) main
+16 25 1
+cfn=(2) parse
+calls=1 50
+16 400 5
+cfi=(2) util.rs
+cfn=(3) hash;fast
+calls=3 20
+17 150 2
+
+fn=(2)
+50 100 1
+cfi=(2)
+cfn=(3)
+calls=2 20
+51 300 4
+
+fl=(2)
+fn=(3)
+20 450 6
+
+totals: 575 8
+
+part: 2
+desc: Trigger: Program termination
+events: Ir Bcm
+
+fl=(1)
+fn=(4) a
+1 10 0
+cfn=(5) b
+calls=1 1
+1 30 0
+
+fn=(5)
+1 20 0
+cfn=(4)
+calls=1 1
+1 5 0
+";
+
+#[test]
+fn folded() {
+    let profile = Profile::parse(PROFILE).unwrap();
+    let part = &profile.parts[0];
+
+    let folded = export::folded(part, "Ir").unwrap();
+    let mut lines: Vec<&str> = folded.lines().collect();
+    lines.sort_unstable();
+    assert_eq!(
+        lines,
+        ["main 25", "main;hash,fast 150", "main;parse 100", "main;parse;hash,fast 300"]
+    );
+
+    // costs are split between the callers by the call costs
+    let folded = export::folded(part, "Bcm").unwrap();
+    assert!(folded.contains("main;parse;hash,fast 4\n"));
+    assert!(folded.contains("main;hash,fast 2\n"));
+
+    assert_eq!(export::folded(part, "Dr"), None);
+}
+
+#[test]
+fn cycles() {
+    let profile = Profile::parse(PROFILE).unwrap();
+    let part = &profile.parts[1];
+    assert_eq!(part.name(), "Program termination");
+
+    // every function is called, so there is no root to start from
+    assert_eq!(export::folded(part, "Ir").unwrap(), "");
+}
+
+#[test]
+fn diamonds() {
+    use std::fmt::Write;
+
+    // `a{n}` calls `b{n}` and `c{n}`, which both call `a{n+1}`: 2^30 paths to `a30`
+    const LEVELS: usize = 30;
+    const COST: u64 = 1 << 40;
+
+    let mut text = String::from("events: Ir\nfl=(1) main.rs\n");
+    for level in 0..LEVELS {
+        let (a, b, c, next) = (3 * level + 1, 3 * level + 2, 3 * level + 3, 3 * level + 4);
+        let half = COST / 2;
+        writeln!(text, "fn=({a}) a{level}").unwrap();
+        writeln!(text, "cfn=({b}) b{level}\ncalls=1 1\n1 {half}").unwrap();
+        writeln!(text, "cfn=({c}) c{level}\ncalls=1 1\n1 {half}").unwrap();
+        for callee in [b, c] {
+            writeln!(text, "fn=({callee})\ncfn=({next}) a{}\ncalls=1 1\n1 {half}", level + 1)
+                .unwrap();
+        }
+    }
+    writeln!(text, "fn=({})\n1 {COST}", 3 * LEVELS + 1).unwrap();
+
+    let profile = Profile::parse(&text).unwrap();
+    let folded = export::folded(&profile.parts[0], "Ir").unwrap();
+
+    // the cheap paths are folded into their callers, the total cost is kept
+    assert!(folded.lines().count() < 100_000);
+    let total: u64 =
+        folded.lines().map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap()).sum();
+    assert!(total.abs_diff(COST) < 100_000, "{total}");
+}
+
+#[test]
+fn part_names() {
+    let mut profile = Profile::parse(PROFILE).unwrap();
+    assert_eq!(profile.parts[0].name(), "first");
+
+    profile.parts[1].desc.clear();
+    assert_eq!(profile.parts[1].name(), "part 2");
+}
+
+#[test]
+fn pprof() {
+    let profile = Profile::parse(PROFILE).unwrap();
+    let pprof = export::pprof(&profile.parts[0], "Ir").unwrap();
+
+    // sample_type { type: 1, unit: 2 }
+    assert_eq!(pprof[..6], [0x0a, 4, 0x08, 1, 0x10, 2]);
+    // sample { location_id: [1], value: [25] }
+    assert_eq!(pprof[6..14], [0x12, 6, 0x0a, 1, 1, 0x12, 1, 25]);
+
+    // string_table, starting with the empty string
+    let table = [&[0x32, 0][..], b"\x32\x02Ir", b"\x32\x05count", b"\x32\x04main"].concat();
+    assert!(pprof.windows(table.len()).any(|window| window == table));
+    assert!(pprof.ends_with(b"\x32\x07util.rs"));
+
+    assert_eq!(export::pprof(&profile.parts[0], "Dr"), None);
+}
+
+#[test]
+fn speedscope() {
+    let profile = Profile::parse(PROFILE).unwrap();
+    let json = export::speedscope(&profile, "Ir").unwrap();
+
+    assert!(json.starts_with(
+        r#"{"$schema":"https://www.speedscope.app/file-format-schema.json","exporter":"crabgrind","name":"./app","#
+    ));
+    assert!(json.contains(
+        r#""frames":[{"name":"main","file":"main.rs"},{"name":"parse","file":"main.rs"},{"name":"hash;fast","file":"util.rs"},{"name":"a","file":"main.rs"},{"name":"b","file":"main.rs"}]"#
+    ));
+    assert!(json.contains(
+        r#"{"type":"sampled","name":"first (Ir)","unit":"none","startValue":0,"endValue":575,"samples":[[0],[0,1],[0,1,2],[0,2]],"weights":[25,100,300,150]}"#
+    ));
+    assert!(json.contains(r#""name":"Program termination (Ir)""#));
+
+    assert_eq!(export::speedscope(&profile, "Dr"), None);
+}
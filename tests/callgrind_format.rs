@@ -0,0 +1,157 @@
+#![cfg(feature = "std")]
+use crabgrind::callgrind::format::*;
+
+const PROFILE: &str = "\
+# callgrind format
+version: 1
+creator: callgrind-3.22.0
+pid: 4242
+cmd:  ./app --flag=1
+part: 2
+
+desc: I1 cache: 32768 B, 64 B, 8-way associative
+desc: Trigger: Client Request: HashMap::insert
+positions: line
+events: Ir Dr
+summary: 575 82
+
+ob=(1) /app
+fl=(1) main.rs
+fn=(1) main
+16 20 2
+cfn=(2) parse
+calls=1 50
+16 400 100
+cfi=(2) util.rs
+cfn=(3) hash
+calls=3 20
++2 150 30
++1 5
+
+fn=(2)
+50 100 10
+cfi=(2)
+cfn=(3)
+calls=2 20
+* 300 40
+cfn=(2)
+calls=1 50
++1 200 20
+
+fl=(2)
+fn=(3)
+20 450 70
+jump=2 +5
++1
+
+totals: 575 82
+";
+
+#[test]
+fn parse() {
+    let profile = Profile::parse(PROFILE).unwrap();
+    assert_eq!(profile.creator.as_deref(), Some("callgrind-3.22.0"));
+    assert_eq!(profile.pid, Some(4242));
+    assert_eq!(profile.cmd.as_deref(), Some("./app --flag=1"));
+
+    let part = profile.dump("HashMap::insert").unwrap();
+    assert_eq!(part.number, 2);
+    assert_eq!(part.events, ["Ir", "Dr"]);
+    assert_eq!(part.summary, [575, 82]);
+    assert_eq!(part.totals, [575, 82]);
+    assert_eq!(part.functions.len(), 3);
+
+    let main = part.function("main").unwrap();
+    assert_eq!(main.object.as_deref(), Some("/app"));
+    assert_eq!(main.file.as_deref(), Some("main.rs"));
+    assert_eq!(main.exclusive, [25, 2]);
+    assert_eq!(main.inclusive, [575, 132]);
+    assert_eq!(main.lines[&16], [20, 2]);
+    assert_eq!(main.lines[&19], [5, 0]);
+
+    // the recursive call is not added
+    let parse = part.function("parse").unwrap();
+    assert_eq!(parse.exclusive, [100, 10]);
+    assert_eq!(parse.inclusive, [400, 50]);
+
+    let hash = part.function("hash").unwrap();
+    assert_eq!(hash.file.as_deref(), Some("util.rs"));
+    assert_eq!(hash.exclusive, [450, 70]);
+    assert_eq!(hash.inclusive, hash.exclusive);
+}
+
+#[test]
+fn call_edges() {
+    let profile = Profile::parse(PROFILE).unwrap();
+    let part = &profile.parts[0];
+    let index = |name| part.functions.iter().position(|f| f.name == name).unwrap();
+
+    let callees: Vec<_> = part
+        .callees(index("main"))
+        .map(|call| (part.functions[call.callee].name.as_str(), call.count, &call.inclusive[..]))
+        .collect();
+    assert_eq!(callees, [("parse", 1, &[400, 100][..]), ("hash", 3, &[150, 30][..])]);
+
+    assert_eq!(part.callers(index("hash")).map(|call| call.count).sum::<u64>(), 5);
+    assert_eq!(part.callers(index("parse")).count(), 2);
+}
+
+#[test]
+fn multiple_parts() {
+    let text = "\
+events: Ir
+part: 1
+fn=(1) a
+1 10
+totals: 10
+part: 2
+fn=(1)
+1 5
+fn=b
+0x10 7
+totals: 12
+";
+    let profile = Profile::parse(text).unwrap();
+    assert_eq!(profile.parts.len(), 2);
+    assert_eq!(profile.parts[0].function("a").unwrap().exclusive, [10]);
+    assert_eq!(profile.parts[1].events, ["Ir"]);
+    assert_eq!(profile.parts[1].function("a").unwrap().exclusive, [5]);
+    assert_eq!(profile.parts[1].function("b").unwrap().lines[&16], [7]);
+}
+
+#[test]
+fn read_dumps() {
+    let dir = std::env::temp_dir().join(format!("crabgrind-dumps.{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let part = |n| format!("events: Ir\nfn=f{n}\n1 {n}\n");
+    for (i, name) in
+        ["out", "out.1", "out.2-01", "out-02", "out.", "out.x", "out1", "out.1.bak", "out.1-"]
+            .iter()
+            .enumerate()
+    {
+        std::fs::write(dir.join(name), part(i + 1)).unwrap();
+    }
+
+    let profile = Profile::read_dumps(dir.join("out"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut functions: Vec<String> =
+        profile.unwrap().parts.iter().map(|part| part.functions[0].name.clone()).collect();
+    functions.sort_unstable();
+    assert_eq!(functions, ["f1", "f2", "f3", "f4"]);
+}
+
+#[test]
+fn syntax_errors() {
+    let err = |text| match Profile::parse(text) {
+        Err(FormatError::Syntax { line, .. }) => line,
+        other => panic!("{other:?}"),
+    };
+
+    assert_eq!(err("events: Ir\nfn=(1)\n"), 2);
+    assert_eq!(err("events: Ir\n1 10\n"), 2);
+    assert_eq!(err("events: Ir\nfn=a\ncalls=1 2\n"), 3);
+    assert_eq!(err("events: Ir\nfn=a\n1 x\n"), 3);
+    assert_eq!(err("events: Ir\nfn=a\n1 1\n-2 1\n"), 4);
+    assert_eq!(err("events: Ir\nfn=a\n0xffffffffffffffff 1\n+1 1\n"), 4);
+}
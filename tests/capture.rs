@@ -0,0 +1,149 @@
+#![cfg(feature = "std")]
+use crabgrind::valgrind::capture::*;
+
+mod common;
+
+const LEAK_SUMMARY: &str = "\
+==4242== LEAK SUMMARY:
+==4242==    definitely lost: 1,070 (+70) bytes in 2 (+1) blocks
+==4242==    indirectly lost: 0 (+0) bytes in 0 (+0) blocks
+==4242==      possibly lost: 48 (+0) bytes in 1 (+0) blocks
+==4242==    still reachable: 2,104 (+0) bytes in 9 (+0) blocks
+==4242==         suppressed: 0 (+0) bytes in 0 (+0) blocks
+==4242== Rerun with --leak-check=full to see details of leaked memory
+";
+
+const INFO_LOCKS: &str = "\
+Lock ga 0x1ffefffc40 {
+   kind   mbRec
+ { W1:thread #1 tid 1 }
+}
+Lock ga 0x4a5c0b0 {
+   kind   rdwr
+ { R2:thread #2 tid 2 R1:thread #3 tid (exited) }
+}
+Lock ga 0x4a5c0f0 {
+   kind   nonRec
+}
+";
+
+#[test]
+fn leak_summary() {
+    let summary = LeakSummary::parse(LEAK_SUMMARY).unwrap();
+
+    assert_eq!(summary.definitely_lost, Leaked { bytes: 1070, blocks: 2 });
+    assert_eq!(summary.indirectly_lost, Leaked::default());
+    assert_eq!(summary.possibly_lost, Leaked { bytes: 48, blocks: 1 });
+    assert_eq!(summary.still_reachable, Leaked { bytes: 2104, blocks: 9 });
+    assert_eq!(summary.suppressed, Leaked::default());
+}
+
+#[test]
+fn leak_summary_no_leaks() {
+    let text = "==1== All heap blocks were freed -- no leaks are possible\n";
+    assert_eq!(LeakSummary::parse(text), Some(LeakSummary::default()));
+    assert_eq!(LeakSummary::parse("==1== HEAP SUMMARY:\n"), None);
+}
+
+#[test]
+fn error_count() {
+    let text = "n_errs_found 3 n_errs_shown 2 (vgdb-error 999999999) checkpoint\n";
+    assert_eq!(ErrorCount::parse(text), Some(ErrorCount { found: 3, shown: 2 }));
+    assert_eq!(ErrorCount::parse("n_errs_found\n"), None);
+    assert_eq!(ErrorCount::parse("n_errs_found 99999999999999999999 n_errs_shown 0\n"), None);
+}
+
+#[test]
+fn info_locks() {
+    let locks = LockInfo::parse_all(INFO_LOCKS);
+    assert_eq!(locks.len(), 3);
+
+    assert_eq!(locks[0].addr, 0x1f_feff_fc40);
+    assert_eq!(locks[0].kind, LockKind::Recursive);
+    assert_eq!(locks[0].holders, [LockHolder { write: true, count: 1, thread: 1 }]);
+
+    assert_eq!(locks[1].kind, LockKind::ReadWrite);
+    assert_eq!(
+        locks[1].holders,
+        [
+            LockHolder { write: false, count: 2, thread: 2 },
+            LockHolder { write: false, count: 1, thread: 3 }
+        ]
+    );
+
+    assert_eq!(locks[2].kind, LockKind::NonRecursive);
+    assert_eq!(locks[2].holders, []);
+}
+
+#[test]
+fn not_captured() {
+    if std::env::var_os(LOG_FILE_ENV).is_none() {
+        assert!(matches!(Log::open(), Err(CaptureError::NotCaptured)));
+    }
+}
+
+#[cfg(feature = "valgrind")]
+mod valgrind {
+    use crabgrind::{
+        helgrind::monitor::InfoLocksCmd,
+        memcheck::monitor::LeakCheckCmd,
+        valgrind::{capture::*, monitor},
+    };
+
+    use super::common::*;
+
+    // Runs the test under `tool`, with the log captured the same way `reexec` does.
+    fn run_captured(tool: &str, test_name: &str, test_fn: impl FnOnce()) {
+        if std::env::var(TEST_RUNNER).is_ok() {
+            return test_fn();
+        }
+
+        let log = std::env::temp_dir().join(format!("crabgrind.{test_name}.log"));
+        let output = std::process::Command::new("valgrind")
+            .arg(format!("--tool={tool}"))
+            .arg(format!("--log-file={}", log.display()))
+            .arg(std::env::current_exe().unwrap())
+            .args([test_name, "--exact", "--no-capture"])
+            .env(TEST_RUNNER, "")
+            .env(LOG_FILE_ENV, &log)
+            .output()
+            .unwrap();
+        let _ = std::fs::remove_file(log);
+
+        if !output.status.success() {
+            inline_panic(output.stdout, output.stderr);
+        }
+    }
+
+    #[test]
+    fn leak_summary() {
+        run_captured("memcheck", "valgrind::leak_summary", || {
+            leak::<64>();
+            let text = monitor_output(&LeakCheckCmd::summary()).unwrap();
+            let summary = LeakSummary::parse(&text).unwrap();
+            assert_eq!(summary.definitely_lost.bytes, 64);
+        });
+    }
+
+    #[test]
+    fn error_count() {
+        run_captured("memcheck", "valgrind::error_count", || {
+            let (result, text) = capture(|| monitor::n_errs_found("msg")).unwrap();
+            result.unwrap();
+            assert_eq!(ErrorCount::parse(&text), Some(ErrorCount { found: 0, shown: 0 }));
+        });
+    }
+
+    #[test]
+    fn info_locks() {
+        run_captured("helgrind", "valgrind::info_locks", || {
+            // Helgrind intercepts `pthread_mutex_*`, not the futex-based `std::sync::Mutex`
+            let mut lock = libc::PTHREAD_MUTEX_INITIALIZER;
+            unsafe { libc::pthread_mutex_lock(&mut lock) };
+            let text = monitor_output(&InfoLocksCmd::all()).unwrap();
+            unsafe { libc::pthread_mutex_unlock(&mut lock) };
+
+            assert!(LockInfo::parse_all(&text).iter().any(|lock| !lock.holders.is_empty()));
+        });
+    }
+}
@@ -18,6 +18,39 @@ fn ad_hoc_event() {
     });
 }
 
+#[test]
+fn event() {
+    valgrind!(dhat --mode=ad-hoc => {
+        for i in 0..3 {
+            dh::event!("labeled", i + 1);
+        }
+        dh::event!("other");
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("7 units in 4 events"));
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn events_by_label() {
+    use crabgrind::dhat::format::{EventTotals, Profile};
+
+    valgrind!(dhat --mode=ad-hoc --dhat-out-file=dhat.out.labels => {
+        dh::event!("first", 10);
+        dh::event!("second");
+        dh::event!("first", 5);
+        dh::ad_hoc_event(100);
+    }, |_: Output| {
+        let profile = Profile::read("dhat.out.labels").unwrap();
+        std::fs::remove_file("dhat.out.labels").unwrap();
+
+        assert_eq!(profile.events("first"), EventTotals { units: 15, events: 2 });
+        assert_eq!(profile.events("second"), EventTotals { units: 1, events: 1 });
+        assert_eq!(profile.events_by_label().len(), 2);
+    });
+}
+
 #[test]
 fn histogram_memory() {
     valgrind!(dhat --mode=heap => {
@@ -27,3 +60,75 @@ fn histogram_memory() {
         unsafe { libc::munmap(ptr, 2048) };
     });
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn format_read() {
+    use crabgrind::dhat::format::{Mode, Profile};
+
+    valgrind!(dhat --mode=heap --dhat-out-file=dhat.out.format => {
+        let v: Vec<u64> = std::hint::black_box(vec![0; 512]);
+        drop(v);
+    }, |_: Output| {
+        let profile = Profile::read("dhat.out.format").unwrap();
+        std::fs::remove_file("dhat.out.format").unwrap();
+
+        assert_eq!(profile.mode, Mode::Heap);
+        assert!(profile.block_lifetimes && profile.block_accesses);
+        assert!(profile.points.iter().any(|point| point.total_bytes == 4096));
+        assert!(profile.top_by_total_bytes(1)[0].total_bytes >= 4096);
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn histogram() {
+    use crabgrind::dhat::{HISTOGRAM_MAX_SIZE, HistogramError, format::Profile};
+
+    #[repr(C)]
+    struct Hot {
+        cold: u64,
+        hot: u64,
+    }
+
+    valgrind!(dhat --mode=heap --dhat-out-file=dhat.out.histogram => {
+        let hot = Box::new(Hot { cold: 0, hot: 0 });
+        dh::histogram(&hot).unwrap();
+        for _ in 0..10 {
+            unsafe { std::ptr::addr_of!(hot.hot).read_volatile() };
+        }
+
+        let slice = vec![0u8; 2048].into_boxed_slice();
+        dh::histogram_slice(&slice).unwrap();
+        std::hint::black_box(&slice[2000..2010]);
+
+        assert_eq!(dh::histogram(&Box::new(())), Err(HistogramError::ZeroSized));
+        let large = vec![0u8; HISTOGRAM_MAX_SIZE + 1].into_boxed_slice();
+        assert_eq!(dh::histogram_slice(&large), Err(HistogramError::TooLarge(HISTOGRAM_MAX_SIZE + 1)));
+        assert_eq!(count_errors(), 0);
+    }, |_: Output| {
+        let profile = Profile::read("dhat.out.histogram").unwrap();
+        std::fs::remove_file("dhat.out.histogram").unwrap();
+
+        let hot = profile.histograms(16).next().unwrap();
+        assert_eq!(hot.accesses_in(0..8), Some(0));
+        assert!(hot.accesses_in(8..16).unwrap() >= 80);
+        assert!(profile.histograms(2048).next().is_some());
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn heap_budget() {
+    let v = dh::assert_test_heap_budget("heap_budget", 1024, 1, || vec![0u8; 1024]);
+    assert_eq!(v.len(), 1024);
+
+    let over = std::panic::catch_unwind(|| {
+        dh::assert_test_heap_budget("heap_budget", 4096, 2, || {
+            (0..3).map(|_| Box::new(0u64)).collect::<Vec<_>>()
+        })
+    });
+    let err = over.unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("exceeded: 48 bytes in 4 blocks"), "{msg}");
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "std")]
+use crabgrind::dhat::format::*;
+
+const HEAP: &str = r#"{"dhatFileVersion":2
+,"mode":"heap","verb":"Allocated"
+,"bklt":true
+,"bkacc":true
+,"tu":"instrs","Mtu":"instr"
+,"tuth":500
+,"cmd":"./app \"quoted\" é"
+,"pid":4242
+,"te":1000
+,"tg":800
+,"pps":
+ [{"tb":1024,"tbk":1,"tl":100
+   ,"mb":1024,"mbk":1
+   ,"gb":1024,"gbk":1
+   ,"fb":0,"fbk":0
+   ,"rb":0,"wb":1024
+   ,"fs":[1,3]
+  }
+ ,{"tb":48,"tbk":3,"tl":30
+   ,"mb":32,"mbk":2
+   ,"gb":16,"gbk":1
+   ,"fb":16,"fbk":1
+   ,"rb":40,"wb":48
+   ,"acc":[-4,3,2,0,1]
+   ,"fs":[2,3]
+  }
+ ]
+,"ftbl":
+ ["[root]"
+ ,"0x4846828: malloc (in /usr/libexec/valgrind/vgpreload_dhat-amd64-linux.so)"
+ ,"0x10A8F3: app::parse (main.rs:10:5)"
+ ,"0x10A9B0: app::main (main.rs:20:9)"
+ ]
+}
+"#;
+
+#[test]
+fn parse_heap() {
+    let profile = Profile::parse(HEAP).unwrap();
+    assert_eq!(profile.version, 2);
+    assert_eq!(profile.mode, Mode::Heap);
+    assert_eq!(profile.cmd, "./app \"quoted\" é");
+    assert_eq!(profile.pid, 4242);
+    assert_eq!(profile.verb, "Allocated");
+    assert_eq!(profile.time_unit, "instrs");
+    assert_eq!(profile.time_end, 1000);
+    assert_eq!(profile.time_global_max, Some(800));
+    assert!(profile.block_lifetimes && profile.block_accesses);
+    assert_eq!(profile.frames.len(), 4);
+
+    let point = &profile.points[1];
+    assert_eq!((point.total_bytes, point.total_blocks), (48, 3));
+    assert_eq!(point.total_lifetimes, Some(30));
+    assert_eq!((point.max_bytes, point.max_blocks), (Some(32), Some(2)));
+    assert_eq!((point.global_max_bytes, point.global_max_blocks), (Some(16), Some(1)));
+    assert_eq!((point.end_bytes, point.end_blocks), (Some(16), Some(1)));
+    assert_eq!((point.reads, point.writes), (Some(40), Some(48)));
+    assert_eq!(point.accesses.as_deref(), Some(&[3, 3, 3, 3, 2, 0, 1][..]));
+    assert_eq!(point.average_size(), 16);
+    assert_eq!(
+        profile.stack(point).collect::<Vec<_>>(),
+        ["0x10A8F3: app::parse (main.rs:10:5)", "0x10A9B0: app::main (main.rs:20:9)"]
+    );
+}
+
+#[test]
+fn histograms() {
+    let profile = Profile::parse(HEAP).unwrap();
+
+    let point = profile.histograms(7).next().unwrap();
+    assert_eq!(point.accesses_in(0..4), Some(12));
+    assert_eq!(point.accesses_in(4..7), Some(3));
+    assert_eq!(point.accesses_in(5..6), Some(0));
+    assert_eq!(point.accesses_in(4..8), None);
+    assert_eq!(profile.points[0].accesses_in(0..1), None);
+    assert_eq!(profile.histograms(16).count(), 0);
+}
+
+#[test]
+fn queries() {
+    let profile = Profile::parse(HEAP).unwrap();
+    assert_eq!(profile.total_bytes(), 1072);
+    assert_eq!(profile.total_blocks(), 4);
+
+    let top = profile.top_by_total_bytes(1);
+    assert_eq!(top, [&profile.points[0]]);
+    let top = profile.top_by(5, |point| point.total_blocks);
+    assert_eq!(top, [&profile.points[1], &profile.points[0]]);
+
+    assert_eq!(profile.never_read().collect::<Vec<_>>(), [&profile.points[0]]);
+    assert_eq!(profile.never_written().count(), 0);
+    assert_eq!(profile.points_in("app::parse").count(), 1);
+    assert_eq!(profile.points_in("app::main").count(), 2);
+}
+
+#[test]
+fn parse_ad_hoc() {
+    let profile = Profile::parse(
+        r#"{"dhatFileVersion":2,"mode":"ad-hoc","verb":"Occurred","bklt":false,"bkacc":false,
+        "bu":"unit","bsu":"units","bksu":"events","tu":"instrs","Mtu":"instr","cmd":"./app",
+        "pid":1,"te":10,"pps":[{"tb":300,"tbk":2,"fs":[1]}],"ftbl":["[root]","0x1: f (a.rs:1)"]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(profile.mode, Mode::AdHoc);
+    assert_eq!(profile.time_global_max, None);
+    let point = &profile.points[0];
+    assert_eq!((point.total_bytes, point.total_blocks), (300, 2));
+    assert_eq!((point.total_lifetimes, point.reads, point.accesses.as_ref()), (None, None, None));
+    assert_eq!(profile.never_read().count(), 0);
+}
+
+#[test]
+fn errors() {
+    let err = |text| Profile::parse(text).unwrap_err().to_string();
+
+    assert_eq!(err("{\n\"mode\": tru}"), "line 2: unexpected character");
+    assert_eq!(err("{} {}"), "line 1: trailing characters");
+    assert_eq!(err(r#"{"mode":"heap""#), "line 1: expected `,` or `}`");
+    assert_eq!(err(r#"{"mode":"sampling"}"#), "missing or invalid field `mode`");
+    assert_eq!(
+        err(r#"{"mode":"heap","ftbl":[],"pps":[{"fs":[]}]}"#),
+        "missing or invalid field `tb`"
+    );
+    assert_eq!(
+        err(r#"{"mode":"heap","ftbl":[],"pps":[{"tb":8,"tbk":1,"acc":[-9,1],"fs":[]}]}"#),
+        "missing or invalid field `acc`"
+    );
+    assert_eq!(
+        err(
+            r#"{"mode":"heap","ftbl":[],"pps":[{"tb":8,"tbk":1,"acc":[-9223372036854775808,1],"fs":[]}]}"#
+        ),
+        "missing or invalid field `acc`"
+    );
+    assert!(matches!(Profile::read("dhat.out.missing"), Err(FormatError::Io(_))));
+}
+
+#[test]
+fn events_by_label() {
+    let profile = Profile::parse(
+        r#"{"dhatFileVersion":2,"mode":"ad-hoc","verb":"Occurred","bklt":false,"bkacc":false,
+        "tu":"instrs","cmd":"./app","pid":1,"te":10,
+        "pps":[{"tb":30,"tbk":3,"fs":[1,2,4]},{"tb":5,"tbk":1,"fs":[1,3,4]},
+               {"tb":7,"tbk":7,"fs":[1,4]},{"tb":2,"tbk":2,"fs":[1,5,4]}],
+        "ftbl":["[root]","0x1: vg_ad_hoc_event (shim.c:3)",
+                "0x2: app::lookup::__crabgrind_event_cache_miss (main.rs:11:13)",
+                "0x3: app::main::__crabgrind_event_cache_miss::h0123456789abcdef (main.rs:20:5)",
+                "0x4: app::main (main.rs:20:5)",
+                "0x5: app::lookup::__crabgrind_event_hit (main.rs:7:13)"]}"#,
+    )
+    .unwrap();
+
+    let labels = profile.events_by_label();
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels["cache_miss"], EventTotals { units: 35, events: 4 });
+    assert_eq!(profile.events("hit"), EventTotals { units: 2, events: 2 });
+    assert_eq!(profile.events("unknown"), EventTotals::default());
+}
@@ -55,6 +55,51 @@ fn trace_var_toggle() {
     });
 }
 
+#[test]
+fn trace_slice() {
+    valgrind!(drd => {
+        let buf = [0u32; 4];
+        let _guard = dd::trace_slice(&buf);
+        unsafe { std::ptr::read_volatile(&buf[3] as *const _) };
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(DRD_TRACE_RE.is_match(stderr));
+    });
+}
+
+#[test]
+fn trace_range() {
+    valgrind!(drd => {
+        let buf = [0u8; 16];
+        let guard = dd::trace_range(buf.as_ptr().cast(), 8);
+        unsafe { std::ptr::read_volatile(&buf[7] as *const _) };
+        drop(guard);
+        unsafe { std::ptr::read_volatile(&buf[8] as *const _) };
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert_eq!(DRD_TRACE_RE.find_iter(stderr).count(), 1);
+    });
+}
+
+#[test]
+fn ignore_slice() {
+    valgrind!(drd --first-race-only=yes => {
+        let addr = race_unsafe(|addr| {
+            let buf = unsafe { std::slice::from_raw_parts(addr, 1) };
+            std::mem::forget(dd::ignore_slice(buf));
+            unsafe { *addr += 1 };
+        });
+        print_addr(addr);
+    }, |output: Output|{
+        let stderr = as_str!(&output.stderr);
+        let stdout = as_str!(&output.stdout);
+
+        let var_addr = parse_addr(stdout);
+
+        assert!(!stderr.contains(var_addr));
+    });
+}
+
 #[test]
 fn annotate_reads_and_writes() {
     valgrind!(drd => {
@@ -71,3 +116,83 @@ fn annotate_reads_and_writes() {
         assert!(num_errors < 2, "{num_errors}");
     });
 }
+
+// Spin barrier for two threads, invisible to DRD without the annotations
+#[derive(Default)]
+struct SpinBarrier(std::sync::atomic::AtomicUsize);
+
+impl SpinBarrier {
+    fn wait(&self) {
+        use std::sync::atomic::Ordering;
+
+        self.0.fetch_add(1, Ordering::AcqRel);
+        while self.0.load(Ordering::Acquire) < 2 {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+#[test]
+fn annotate_barrier() {
+    valgrind!(drd => {
+        let barrier = SpinBarrier::default();
+        let annotated = dd::annotate_barrier(&barrier, 2);
+        let mut data = 0u64;
+        let data_addr = &mut data as *mut u64 as usize;
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                unsafe { *(data_addr as *mut u64) = 42 };
+                annotated.wait(|| barrier.wait());
+            });
+            annotated.wait(|| barrier.wait());
+            assert_eq!(unsafe { *(data_addr as *const u64) }, 42);
+        });
+        drop(annotated);
+
+        assert_eq!(crabgrind::valgrind::count_errors(), 0);
+    });
+}
+
+#[test]
+fn annotate_pcq() {
+    valgrind!(drd => {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // single-slot queue, 0 when empty
+        let slot = AtomicUsize::new(0);
+        let pcq = dd::annotate_pcq(&slot);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let element = Box::into_raw(Box::new(42u64));
+                pcq.put();
+                slot.store(element as usize, Ordering::Relaxed);
+            });
+            let element = loop {
+                match slot.load(Ordering::Relaxed) {
+                    0 => std::hint::spin_loop(),
+                    element => break element as *mut u64,
+                }
+            };
+            pcq.get();
+            assert_eq!(*unsafe { Box::from_raw(element) }, 42);
+        });
+
+        assert_eq!(crabgrind::valgrind::count_errors(), 0);
+    });
+}
+
+#[test]
+fn annotate_memory_ranges() {
+    valgrind!(drd => {
+        let data = [0u8; 8];
+        dd::annotate_happens_before(data.as_ptr().cast());
+        dd::annotate_happens_after(data.as_ptr().cast());
+        dd::annotate_condvar_signal(data.as_ptr().cast());
+        dd::annotate_condvar_wait(data.as_ptr().cast());
+        dd::annotate_swap_memory_range(data.as_ptr().cast(), data.len());
+        dd::annotate_publish_memory_range(data.as_ptr().cast(), data.len());
+        assert_eq!(crabgrind::valgrind::count_errors(), 0);
+    });
+}
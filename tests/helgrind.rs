@@ -42,6 +42,66 @@ fn clean_memory() {
     });
 }
 
+#[test]
+fn annotate_benign_race() {
+    valgrind!(helgrind => {
+        let addr = race_unsafe(|addr|{
+            hg::annotate_benign_race_sized(addr as _, 2);
+            unsafe { *addr += 1 };
+        });
+        print_addr(addr);
+    }, |output: Output|{
+        let stderr = as_str!(&output.stderr);
+        let stdout = as_str!(&output.stdout);
+
+        let var_addr = parse_addr(stdout);
+
+        assert!(!stderr.contains(var_addr));
+    });
+}
+
+#[test]
+fn disable_checking() {
+    valgrind!(helgrind => {
+        let addr = race_unsafe(|addr|{
+            let _unchecked = hg::disable_checking(unsafe { &*addr });
+            unsafe { *addr += 1 };
+        });
+        print_addr(addr);
+    }, |output: Output|{
+        let stderr = as_str!(&output.stderr);
+        let stdout = as_str!(&output.stdout);
+
+        let var_addr = parse_addr(stdout);
+
+        assert!(!stderr.contains(var_addr));
+    });
+}
+
+#[test]
+fn abits() {
+    valgrind!(helgrind => {
+        let data = [0u8; 8];
+        let mut abits = [0u8; 8];
+        assert_eq!(hg::abits(data.as_ptr().cast(), &mut abits), Some(8));
+        assert_eq!(abits, [0xFF; 8]);
+
+        let mut abits = [0u8; 8];
+        assert_eq!(hg::abits(std::ptr::null(), &mut abits), Some(0));
+        assert_eq!(abits, [0; 8]);
+    });
+}
+
+#[test]
+fn annotate_new_memory() {
+    valgrind!(helgrind => {
+        let data = [0u8; 8];
+        hg::annotate_new_memory(data.as_ptr().cast(), data.len());
+        hg::annotate_thread_name(cstr!("worker"));
+        assert_eq!(crabgrind::valgrind::count_errors(), 0);
+    });
+}
+
 #[test]
 fn annotate_happens() {
     valgrind!(helgrind => {
@@ -63,3 +123,21 @@ fn annotate_rwlock() {
         hg::annotate_rwlock_destroy(&var as *const _ as _);
     });
 }
+
+#[test]
+fn monitor_commands() {
+    use hg::monitor::{AccessHistoryCmd, Command, InfoLocksCmd};
+
+    valgrind!(helgrind --history-level=full => {
+        let (addr, ()) = contention(|_| ());
+        // Helgrind intercepts `pthread_mutex_*`, not the futex-based `std::sync::Mutex`
+        let mut lock = libc::PTHREAD_MUTEX_INITIALIZER;
+        unsafe { libc::pthread_mutex_lock(&mut lock) };
+        InfoLocksCmd::all().run().unwrap();
+        unsafe { libc::pthread_mutex_unlock(&mut lock) };
+        AccessHistoryCmd::new(addr.cast(), 2).run().unwrap();
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("Lock ga"));
+    });
+}
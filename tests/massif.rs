@@ -0,0 +1,87 @@
+#![cfg(feature = "valgrind")]
+use crabgrind::{massif as ms, valgrind::MonitorError};
+
+use std::path::PathBuf;
+
+mod common;
+use common::*;
+
+fn out_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("crabgrind.{name}.massif"))
+}
+
+fn read_out_file(name: &str) -> String {
+    let path = out_file(name);
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    content
+}
+
+#[test]
+fn snapshot() {
+    valgrind!(massif => {
+        let path = out_file("snapshot");
+        assert!(ms::snapshot(path.to_str().unwrap()).is_ok());
+    }, |_| {
+        let content = read_out_file("snapshot");
+        assert!(content.contains("snapshot=0"));
+        assert!(content.contains("heap_tree=empty"));
+    });
+}
+
+#[test]
+fn detailed_snapshot() {
+    valgrind!(massif => {
+        let path = out_file("detailed_snapshot");
+        let v = std::hint::black_box(vec![0u8; 4096]);
+        assert!(ms::detailed_snapshot(path.to_str().unwrap()).is_ok());
+        drop(v);
+    }, |_| {
+        let content = read_out_file("detailed_snapshot");
+        assert!(content.contains("heap_tree=detailed"));
+    });
+}
+
+#[test]
+fn all_snapshots() {
+    valgrind!(massif => {
+        let path = out_file("all_snapshots");
+        assert!(ms::all_snapshots(path.to_str().unwrap()).is_ok());
+    }, |_| {
+        let content = read_out_file("all_snapshots");
+        assert!(content.starts_with("desc:"));
+    });
+}
+
+#[test]
+fn capture() {
+    valgrind!(massif => {
+        let before = out_file("capture_before");
+        let after = out_file("capture_after");
+        let guard = ms::capture(before.to_str().unwrap(), after.to_str().unwrap());
+        let v = std::hint::black_box(vec![0u8; 4096]);
+        drop(guard);
+        drop(v);
+    }, |_| {
+        assert!(read_out_file("capture_before").contains("heap_tree=detailed"));
+        assert!(read_out_file("capture_after").contains("heap_tree=detailed"));
+    });
+}
+
+#[test]
+fn invalid_path() {
+    valgrind!(massif => {
+        assert_eq!(ms::snapshot(""), Err(MonitorError::InvalidCommand));
+        assert_eq!(ms::snapshot("massif out"), Err(MonitorError::InvalidCommand));
+        assert_eq!(ms::snapshot("x".repeat(4096).as_str()), Err(MonitorError::InvalidCommand));
+    });
+}
+
+#[test]
+fn not_massif() {
+    valgrind!(memcheck => {
+        if crabgrind::VALGRIND_VERSION >= (3, 27) {
+            assert_eq!(ms::snapshot(None), Err(MonitorError::WrongTool));
+        }
+    });
+}
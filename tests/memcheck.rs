@@ -204,6 +204,71 @@ fn vbits() {
     });
 }
 
+#[test]
+fn monitor_leak_check() {
+    use mc::monitor::{Command, LeakCheckCmd, LeakKind};
+
+    valgrind!(memcheck --leak-check=no => {
+        leak::<70>();
+        LeakCheckCmd::full().kinds(&[LeakKind::Definite]).limited(1).run().unwrap();
+    }, |output: Output|{
+        let stderr = as_str!(&output.stderr);
+        let definitely_lost = lost(&DEFINITELY_LOST_RE, stderr);
+        assert_eq!(definitely_lost, 70);
+    });
+}
+
+#[test]
+fn monitor_block_list() {
+    use mc::monitor::{BlockListCmd, Command, LeakCheckCmd, LeakKind};
+
+    valgrind!(memcheck --leak-check=no => {
+        leak::<80>();
+        LeakCheckCmd::full().kinds(&[LeakKind::Definite]).run().unwrap();
+        BlockListCmd::record(1).run().unwrap();
+    }, |output: Output|{
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("80 bytes in 1 blocks are definitely lost"));
+    });
+}
+
+#[test]
+fn monitor_who_points_at() {
+    use mc::monitor::{Command, WhoPointsAtCmd};
+
+    valgrind!(memcheck => {
+        let data = Box::new(0u64);
+        let ptr = &*data as *const u64;
+        print_addr(ptr);
+        WhoPointsAtCmd::new(ptr.cast(), 1).run().unwrap();
+    }, |output: Output|{
+        let stderr = as_str!(&output.stderr);
+        let stdout = as_str!(&output.stdout);
+        let addr = parse_addr(stdout);
+
+        assert!(stderr.contains("Searching for pointers to"));
+        assert!(stderr.to_lowercase().contains(&addr.to_lowercase()));
+    });
+}
+
+#[test]
+fn monitor_memory() {
+    use mc::monitor::{CheckMemoryCmd, Command, GetVBitsCmd, MakeMemoryCmd, XbCmd};
+
+    valgrind!(memcheck => {
+        let data = [0u8; 8];
+        let ptr = data.as_ptr().cast();
+
+        MakeMemoryCmd::new(mc::MemState::Undefined, ptr, 8).run().unwrap();
+        assert!(mc::check_mem_defined(ptr, 8).is_err());
+        CheckMemoryCmd::addressable(ptr, 8).run().unwrap();
+        GetVBitsCmd::new(ptr, 8).run().unwrap();
+        XbCmd::new(ptr, 8).run().unwrap();
+        MakeMemoryCmd::new(mc::MemState::Defined, ptr, 8).run().unwrap();
+        assert!(mc::check_mem_defined(ptr, 8).is_ok());
+    });
+}
+
 fn lost(re: &regex::Regex, stderr: &str) -> u32 {
     let lost = &re.captures(stderr).expect("nothing lost, definitely")[1];
     lost.parse().unwrap()
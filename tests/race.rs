@@ -0,0 +1,76 @@
+#![cfg(feature = "valgrind")]
+use crabgrind::{race, valgrind::count_errors};
+
+use std::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+mod common;
+use common::*;
+
+struct Flagged {
+    ready: AtomicBool,
+    value: UnsafeCell<u64>,
+}
+
+unsafe impl Sync for Flagged {}
+
+// Hands a value over through a flag, annotated through the facade
+fn hand_off() {
+    let shared = &Flagged { ready: AtomicBool::new(false), value: UnsafeCell::new(0) };
+    let addr = || (&shared.ready as *const AtomicBool).cast();
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            race::thread_name(cstr!("producer"));
+            unsafe { *shared.value.get() = 42 };
+            race::happens_before(addr());
+            shared.ready.store(true, Ordering::Relaxed);
+        });
+        while !shared.ready.load(Ordering::Relaxed) {
+            std::hint::spin_loop();
+        }
+        race::happens_after(addr());
+        assert_eq!(unsafe { *shared.value.get() }, 42);
+    });
+}
+
+// Increments a counter racily from two threads, announced as a benign race
+fn benign_counter() {
+    let shared = &Flagged { ready: AtomicBool::new(false), value: UnsafeCell::new(0) };
+    race::benign(&shared.value);
+
+    std::thread::scope(|s| {
+        s.spawn(|| unsafe { *shared.value.get() += 1 });
+        unsafe { *shared.value.get() += 1 };
+    });
+}
+
+#[test]
+fn helgrind() {
+    valgrind!(helgrind => {
+        if crabgrind::VALGRIND_VERSION >= (3, 27) {
+            assert_eq!(race::detector(), Some(race::Detector::Helgrind));
+        }
+        hand_off();
+        benign_counter();
+        assert_eq!(count_errors(), 0);
+    });
+}
+
+#[test]
+fn drd() {
+    valgrind!(drd => {
+        if crabgrind::VALGRIND_VERSION >= (3, 27) {
+            assert_eq!(race::detector(), Some(race::Detector::DRD));
+        }
+        hand_off();
+        benign_counter();
+
+        let block = vec![0u8; 16];
+        race::new_memory(block.as_ptr_range());
+        let _ignoring = race::ignore_accesses();
+        assert_eq!(count_errors(), 0);
+    });
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "std")]
+use crabgrind::valgrind::{LogDestination, REEXEC_ENV, ToolConfig};
+
+mod common;
+
+#[test]
+fn command_line() {
+    let cmd = ToolConfig::new("memcheck")
+        .suppressions("a.supp")
+        .suppressions("b.supp")
+        .log(LogDestination::File("memcheck.%p.log".into()))
+        .flags(["--leak-check=full", "--error-exitcode=1"])
+        .command()
+        .unwrap();
+
+    let args: Vec<_> = cmd.get_args().map(|arg| arg.to_str().unwrap()).collect();
+    let exe = std::env::current_exe().unwrap();
+
+    assert_eq!(cmd.get_program(), "valgrind");
+    assert_eq!(
+        args[..6],
+        [
+            "--tool=memcheck",
+            "--suppressions=a.supp",
+            "--suppressions=b.supp",
+            "--log-file=memcheck.%p.log",
+            "--leak-check=full",
+            "--error-exitcode=1"
+        ]
+    );
+    assert_eq!(args[6], exe.to_str().unwrap());
+    assert!(cmd.get_envs().any(|(key, _)| key == REEXEC_ENV));
+}
+
+#[test]
+fn program_args() {
+    let cmd = ToolConfig::new("dhat").args(["one_test", "--exact"]).command().unwrap();
+
+    let args: Vec<_> = cmd.get_args().map(|arg| arg.to_str().unwrap()).collect();
+    assert_eq!(args[2..], ["one_test", "--exact"]);
+}
+
+#[test]
+fn log_destinations() {
+    let log_arg = |log| {
+        let cmd = ToolConfig::new("drd").log(log).command().unwrap();
+        cmd.get_args().nth(1).unwrap().to_str().unwrap().to_owned()
+    };
+
+    assert_eq!(log_arg(LogDestination::Fd(9)), "--log-fd=9");
+    assert_eq!(
+        log_arg(LogDestination::Socket("127.0.0.1:1500".into())),
+        "--log-socket=127.0.0.1:1500"
+    );
+    assert!(ToolConfig::new("drd").log(LogDestination::Captured).command().is_err());
+    assert_eq!(log_arg(LogDestination::Stderr), std::env::current_exe().unwrap().to_str().unwrap());
+}
+
+#[cfg(feature = "valgrind")]
+#[test]
+fn reexec() {
+    use common::*;
+    use crabgrind::valgrind::{self as vg, RunningMode};
+
+    if std::env::var(TEST_RUNNER).is_ok() {
+        vg::reexec(ToolConfig::new("memcheck").flag("--error-exitcode=3")).unwrap();
+        assert_eq!(vg::running_mode(), RunningMode::Valgrind);
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["reexec", "--exact", "--no-capture"])
+        .env(TEST_RUNNER, "")
+        .output()
+        .unwrap();
+
+    if !output.status.success() {
+        inline_panic(output.stdout, output.stderr);
+    }
+    assert!(as_str!(&output.stderr).contains("Memcheck"));
+}
@@ -62,3 +62,211 @@ fn vbits() {
 
     assert!(mc::vbits(data.as_ptr() as _, &mut vbits).is_ok());
 }
+
+#[test]
+fn helgrind_abits() {
+    let data = [0u8; 4];
+    let mut abits = [0u8; 4];
+
+    assert!(crabgrind::helgrind::abits(data.as_ptr() as _, &mut abits).is_none());
+    assert_eq!(abits, [0; 4]);
+
+    let _unchecked = crabgrind::helgrind::disable_checking(&data);
+}
+
+#[test]
+fn drd_annotations() {
+    let barrier = std::sync::Barrier::new(1);
+    let annotated = crabgrind::drd::annotate_barrier(&barrier, 1);
+    assert!(annotated.wait(|| barrier.wait()).is_leader());
+
+    let buf = [0u32; 4];
+    let _tracing = crabgrind::drd::trace_slice(&buf);
+    let _ignoring = crabgrind::drd::ignore_slice(&buf);
+    let _range = crabgrind::drd::trace_range(buf.as_ptr().cast(), 16);
+
+    let queue = [0u8; 4];
+    let pcq = crabgrind::drd::annotate_pcq(&queue);
+    pcq.put();
+    pcq.get();
+}
+
+#[test]
+fn race() {
+    use crabgrind::race;
+
+    assert_eq!(race::detector(), None);
+
+    let value = 0u64;
+    race::benign(&value);
+    race::thread_name(cstr!("worker"));
+    race::happens_before((&value as *const u64).cast());
+    race::happens_after((&value as *const u64).cast());
+    let _ignoring = race::ignore_accesses();
+
+    let block = [0u8; 16];
+    let range = block.as_ptr_range();
+    race::new_memory(range.clone());
+    race::new_memory(range.end..range.start);
+}
+
+#[test]
+fn massif_snapshot() {
+    assert!(crabgrind::massif::snapshot(None).is_ok());
+    assert!(crabgrind::massif::detailed_snapshot("massif.out").is_ok());
+}
+
+#[test]
+fn monitor_core_commands() {
+    assert!(vg::monitor::n_errs_found("msg").is_ok());
+    assert!(vg::monitor::kill().is_ok());
+}
+
+#[test]
+fn memcheck_monitor_commands() {
+    use mc::monitor::{LeakHeuristic::*, LeakKind::*, *};
+
+    let cmd = LeakCheckCmd::full().kinds(&[Definite, Possible]).limited(20).increase();
+    assert_eq!(cmd.to_string(), "leak_check full kinds definite,possible increased limited 20");
+    assert!(cmd.run().is_ok());
+
+    let cmd = LeakCheckCmd::summary().kinds(&[Reachable, Possible, Indirect, Definite]);
+    assert_eq!(
+        cmd.heuristics(&[]).any().to_string(),
+        "leak_check summary kinds all heuristics none any"
+    );
+    assert_eq!(LeakCheckCmd::xtleak().kinds(&[]).to_string(), "leak_check xtleak kinds none");
+
+    let cmd = BlockListCmd::records(2..=5).unwrap().unlimited().heuristics(&[StdString, NewArray]);
+    assert_eq!(cmd.to_string(), "block_list 2..5 unlimited heuristics stdstring,newarray");
+    assert_eq!(BlockListCmd::record(3).limited(1).to_string(), "block_list 3 limited 1");
+    assert_eq!(BlockListCmd::records(5..=2), Err(vg::MonitorError::InvalidCommand));
+
+    let ptr = 0x1000 as *const c_void;
+    assert_eq!(XbCmd::new(ptr, 8).to_string(), "xb 0x1000 8");
+    assert_eq!(GetVBitsCmd::new(ptr, 8).to_string(), "get_vbits 0x1000 8");
+    assert_eq!(WhoPointsAtCmd::new(ptr, 1).to_string(), "who_points_at 0x1000 1");
+    assert_eq!(CheckMemoryCmd::defined(ptr, 4).to_string(), "check_memory defined 0x1000 4");
+    assert_eq!(
+        MakeMemoryCmd::new(mc::MemState::DefinedIfAddressable, ptr, 4).to_string(),
+        "make_memory Definedifaddressable 0x1000 4"
+    );
+    assert_eq!(XtMemoryCmd::new("xt.kcg").unwrap().to_string(), "xtmemory xt.kcg");
+    assert_eq!(XtMemoryCmd::new(None).unwrap().to_string(), "xtmemory");
+    assert_eq!(XtMemoryCmd::new("xt kcg"), Err(vg::MonitorError::InvalidCommand));
+    assert_eq!(XtMemoryCmd::new(""), Err(vg::MonitorError::InvalidCommand));
+}
+
+#[test]
+fn tool_monitor_commands() {
+    use crabgrind::{callgrind::monitor as cl, helgrind::monitor as hg};
+
+    assert_eq!(cl::DumpCmd::new("phase 1").to_string(), "dump phase 1");
+    assert_eq!(cl::InstrumentationCmd::query().to_string(), "instrumentation");
+    assert_eq!(cl::InstrumentationCmd::off().to_string(), "instrumentation off");
+    assert!(cl::Command::run(&cl::ZeroCmd).is_ok());
+
+    let ptr = 0x2000 as *const c_void;
+    assert_eq!(hg::InfoLocksCmd::lock(ptr).to_string(), "info locks 0x2000");
+    assert_eq!(hg::AccessHistoryCmd::new(ptr, 4).to_string(), "accesshistory 0x2000 4");
+    assert!(hg::Command::run(&hg::InfoLocksCmd::all()).is_ok());
+}
+
+#[crabgrind::profile(dump = "stub", zero = true)]
+fn profiled(n: u8) -> u8 {
+    if n == 0 {
+        return 0;
+    }
+    n + 1
+}
+
+#[test]
+fn profile() {
+    assert_eq!(profiled(0), 0);
+    assert_eq!(profiled(1), 2);
+}
+
+#[test]
+fn profile_reason() {
+    assert_eq!(crabgrind::callgrind::__reason(b"stub\0").to_bytes(), b"stub");
+    assert!(std::panic::catch_unwind(|| crabgrind::callgrind::__reason(b"a\0b\0")).is_err());
+    assert!(std::panic::catch_unwind(|| crabgrind::callgrind::__reason(b"ab")).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn bench() {
+    use crabgrind::bench::{Bench, BenchError, Tool};
+
+    let report = Bench::new(Tool::Callgrind).bench("noop", || ()).run().unwrap();
+    assert!(report.results.is_empty());
+    let result = Bench::new(Tool::Callgrind).bench("two words", || ()).run();
+    assert!(matches!(result, Err(BenchError::InvalidName(_))));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reexec() {
+    use crabgrind::valgrind::{LogDestination, ToolConfig};
+
+    assert!(vg::reexec(ToolConfig::new("memcheck")).is_ok());
+    assert!(vg::reexec(ToolConfig::new("memcheck").log(LogDestination::Captured)).is_ok());
+}
+
+#[test]
+fn dump_stats_macro() {
+    let i = 1;
+    crabgrind::callgrind::dump_stats!();
+    crabgrind::callgrind::dump_stats!("iteration {i}");
+
+    let mut dumper = crabgrind::callgrind::Dumper::new("stub");
+    dumper.dump();
+    dumper.dump_phase(i);
+    assert_eq!(dumper.count(), 2);
+}
+
+#[cfg(all(feature = "signals", unix))]
+#[test]
+fn install_signal_handlers() {
+    crabgrind::callgrind::install_signal_handlers().unwrap();
+
+    // the default disposition is kept
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    unsafe { libc::sigaction(libc::SIGUSR1, std::ptr::null(), &mut action) };
+    assert_eq!(action.sa_sigaction, libc::SIG_DFL);
+}
+
+#[test]
+fn dhat_event() {
+    let weight = 3;
+    crabgrind::dhat::event!("stub");
+    crabgrind::dhat::event!("stub_weighted", weight);
+    crabgrind::dhat::event!("stub_weighted", Some(weight * 2));
+}
+
+#[test]
+fn dhat_histogram() {
+    use crabgrind::dhat::{self, HISTOGRAM_MAX_SIZE, HistogramError};
+
+    assert_eq!(dhat::histogram(&Box::new(0u64)), Ok(()));
+    assert_eq!(dhat::histogram(&Box::new(())), Err(HistogramError::ZeroSized));
+    assert_eq!(
+        dhat::histogram_slice(&Vec::<u8>::new().into_boxed_slice()),
+        Err(HistogramError::ZeroSized)
+    );
+
+    let large = vec![0u8; HISTOGRAM_MAX_SIZE + 1].into_boxed_slice();
+    assert_eq!(
+        dhat::histogram_slice(&large),
+        Err(HistogramError::TooLarge(HISTOGRAM_MAX_SIZE + 1))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn heap_budget() {
+    let v = crabgrind::dhat::assert_heap_budget(0, 0, || vec![1, 2, 3]);
+    assert_eq!(v, [1, 2, 3]);
+    let v = crabgrind::dhat::assert_test_heap_budget("heap_budget", 0, 0, || vec![1, 2, 3]);
+    assert_eq!(v, [1, 2, 3]);
+}
@@ -0,0 +1,230 @@
+#[cfg(feature = "std")]
+use crabgrind::sync::HgArc;
+use crabgrind::sync::{
+    Annotated, AnnotatedAtomicBool, AnnotatedAtomicPtr, AnnotatedAtomicUsize, RawLock, RawRwLock,
+    RefCount, RwSpinLock, SpinMutex,
+};
+
+use std::{cell::UnsafeCell, ptr, sync::atomic::Ordering};
+#[cfg(feature = "valgrind")]
+use {crabgrind::valgrind::count_errors, std::process::Output};
+
+mod common;
+#[cfg(feature = "valgrind")]
+use common::*;
+
+struct Shared<L> {
+    lock: L,
+    value: UnsafeCell<u64>,
+}
+
+unsafe impl<L: Sync> Sync for Shared<L> {}
+
+impl<L: RawLock + Sync> Shared<L> {
+    fn new(lock: L) -> Self {
+        Self { lock, value: UnsafeCell::new(0) }
+    }
+
+    fn increment(&self, threads: usize, times: usize) -> u64 {
+        std::thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| {
+                    for _ in 0..times {
+                        self.lock.lock();
+                        unsafe { *self.value.get() += 1 };
+                        self.lock.unlock();
+                    }
+                });
+            }
+        });
+        unsafe { *self.value.get() }
+    }
+}
+
+#[test]
+fn spin_mutex() {
+    let lock = SpinMutex::new();
+    assert!(lock.try_lock());
+    assert!(lock.is_locked());
+    assert!(!lock.try_lock());
+    lock.unlock();
+    assert!(!lock.is_locked());
+
+    assert_eq!(Shared::new(SpinMutex::new()).increment(4, 1000), 4000);
+}
+
+#[test]
+fn rw_spin_lock() {
+    let lock = RwSpinLock::new();
+    assert!(lock.try_lock_shared());
+    assert!(lock.try_lock_shared());
+    assert_eq!(lock.readers(), 2);
+    assert!(!lock.try_lock());
+
+    lock.unlock_shared();
+    lock.unlock_shared();
+    assert!(lock.try_lock());
+    assert!(lock.is_locked_exclusive());
+    assert!(!lock.try_lock_shared());
+    lock.unlock();
+    assert_eq!(lock.readers(), 0);
+
+    assert_eq!(Shared::new(RwSpinLock::new()).increment(4, 1000), 4000);
+}
+
+#[test]
+fn annotated() {
+    let lock = Annotated::new(RwSpinLock::new());
+    lock.lock_shared();
+    assert_eq!(lock.inner().readers(), 1);
+    assert!(!lock.try_lock());
+    lock.unlock_shared();
+    assert!(lock.try_lock());
+    lock.unlock();
+
+    assert_eq!(Shared::new(Annotated::new(SpinMutex::new())).increment(4, 1000), 4000);
+}
+
+#[test]
+fn annotated_atomics() {
+    let n = AnnotatedAtomicUsize::new(1);
+    assert_eq!(n.fetch_add(2, Ordering::AcqRel), 1);
+    assert_eq!(n.swap(5, Ordering::SeqCst), 3);
+    assert_eq!(n.compare_exchange(4, 6, Ordering::AcqRel, Ordering::Acquire), Err(5));
+    assert_eq!(n.compare_exchange(5, 6, Ordering::AcqRel, Ordering::Acquire), Ok(5));
+    assert_eq!(n.fetch_max(8, Ordering::Release), 6);
+    n.store(9, Ordering::Release);
+    assert_eq!(n.load(Ordering::Acquire), 9);
+    assert_eq!(n.into_inner(), 9);
+
+    let flag = AnnotatedAtomicBool::default();
+    assert!(!flag.fetch_or(true, Ordering::AcqRel));
+    assert!(flag.as_inner().load(Ordering::Relaxed));
+
+    let mut value = 7;
+    let mut p = AnnotatedAtomicPtr::new(ptr::null_mut());
+    p.store(&mut value, Ordering::Release);
+    assert_eq!(unsafe { *p.load(Ordering::Acquire) }, 7);
+    *p.get_mut() = ptr::null_mut();
+    assert!(p.into_inner().is_null());
+}
+
+#[test]
+fn ref_count() {
+    let count = RefCount::new(1);
+    assert!(count.is_unique());
+    count.increment();
+    assert_eq!(count.get(), 2);
+    assert!(!count.is_unique());
+    assert!(!count.decrement());
+    assert!(count.decrement());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn hg_arc() {
+    let mut arc = HgArc::new(vec![1]);
+    HgArc::get_mut(&mut arc).unwrap().push(2);
+
+    let clone = HgArc::clone(&arc);
+    assert!(HgArc::ptr_eq(&arc, &clone));
+    assert_eq!(HgArc::strong_count(&arc), 2);
+    assert!(HgArc::get_mut(&mut arc).is_none());
+
+    let arc = HgArc::try_unwrap(arc).unwrap_err();
+    std::thread::spawn(move || assert_eq!(*clone, [1, 2])).join().unwrap();
+    assert_eq!(HgArc::try_unwrap(arc).unwrap(), [1, 2]);
+}
+
+// Hands a value over from a writer to a reader through an annotated flag
+#[cfg(feature = "valgrind")]
+fn hand_off(store: Ordering, load: Ordering) {
+    let shared = &Shared { lock: AnnotatedAtomicBool::new(false), value: UnsafeCell::new(0) };
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            unsafe { *shared.value.get() = 42 };
+            shared.lock.store(true, store);
+        });
+        s.spawn(|| {
+            while !shared.lock.load(load) {
+                std::hint::spin_loop();
+            }
+            assert_eq!(unsafe { *shared.value.get() }, 42);
+        });
+    });
+}
+
+#[cfg(feature = "valgrind")]
+#[test]
+fn helgrind_annotated_atomics() {
+    valgrind!(helgrind => {
+        hand_off(Ordering::Release, Ordering::Acquire);
+        assert_eq!(count_errors(), 0);
+
+        hand_off(Ordering::Relaxed, Ordering::Relaxed);
+        assert!(count_errors() > 0);
+    });
+}
+
+#[cfg(all(feature = "valgrind", feature = "std"))]
+#[test]
+fn helgrind_hg_arc() {
+    valgrind!(helgrind => {
+        let arc = HgArc::new(std::sync::Mutex::new(Vec::new()));
+        let threads: Vec<_> = (0..2)
+            .map(|i| {
+                let arc = HgArc::clone(&arc);
+                std::thread::spawn(move || arc.lock().unwrap().push(i))
+            })
+            .collect();
+        drop(arc);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(count_errors(), 0);
+    });
+}
+
+#[cfg(feature = "valgrind")]
+#[test]
+fn helgrind_annotated() {
+    valgrind!(helgrind => {
+        assert_eq!(Shared::new(Annotated::new(SpinMutex::new())).increment(2, 100), 200);
+
+        let shared = &Shared::new(Annotated::new(RwSpinLock::new()));
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                shared.lock.lock();
+                unsafe { *shared.value.get() = 1 };
+                shared.lock.unlock();
+            });
+            s.spawn(|| {
+                shared.lock.lock_shared();
+                let _ = unsafe { *shared.value.get() };
+                shared.lock.unlock_shared();
+            });
+        });
+        assert_eq!(count_errors(), 0);
+    });
+}
+
+#[cfg(feature = "valgrind")]
+#[test]
+fn helgrind_not_annotated() {
+    valgrind!(helgrind => {
+        Shared::new(SpinMutex::new()).increment(2, 100);
+        assert!(count_errors() > 0);
+    });
+}
+
+#[cfg(feature = "valgrind")]
+#[test]
+fn drd_annotated() {
+    valgrind!(drd => {
+        assert_eq!(Shared::new(Annotated::new(SpinMutex::new())).increment(2, 100), 200);
+        assert_eq!(count_errors(), 0);
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("ERROR SUMMARY: 0 errors"));
+    });
+}
@@ -53,6 +53,26 @@ fn monitor_command() {
     });
 }
 
+#[test]
+fn monitor_core_commands() {
+    use vg::monitor as mon;
+
+    valgrind!(memcheck => {
+        assert!(mon::set_output(mon::OutputMode::Log).is_ok());
+        assert!(mon::set_vgdb_error(999).is_ok());
+        assert!(mon::all_errors(true).is_ok());
+        assert!(mon::n_errs_found("cg_n_errs_found_msg").is_ok());
+        assert!(mon::scheduler().is_ok());
+        assert!(mon::stats().is_ok());
+        assert!(mon::memory(false).is_ok());
+        assert!(mon::expensive_sanity_check().is_ok());
+        assert!(mon::translate(vg::count_errors as *const c_void, None).is_ok());
+    }, |output: Output| {
+        let stderr = as_str!(&output.stderr);
+        assert!(stderr.contains("cg_n_errs_found_msg"));
+    });
+}
+
 #[test]
 fn println_macro() {
     valgrind!(memcheck => {
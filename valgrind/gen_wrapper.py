@@ -28,29 +28,32 @@ def c_enum_epi(tool):
     return f"}} {tool};"
 
 
-def c_enum_variant_def(api, vname, vvar, n):
-    return f"""#ifdef {api}
+def c_enum_variant_def(guard, vname, vvar, n):
+    return f"""#ifdef {guard}
 {TAB}{vname} = {vvar},
 #else
 {TAB}{vname} = {n},
 #endif"""
 
 
+# `API = CONST, version[, guard]`, the guard is the macro checked instead of `API` when several
+# headers define the same one, e.g. `ANNOTATE_HAPPENS_BEFORE` in both `helgrind.h` and `drd.h`
 def variant_info(api):
     vname, vvar = api.split("=")
-    vvar, version = vvar.split(",")
-    return vname, variant_name(vname), vvar.strip(), version.strip()
+    vvar, version, *guard = vvar.split(",")
+    guard = guard[0].strip() if guard else vname
+    return vname, variant_name(vname), vvar.strip(), version.strip(), guard
 
     
 def variant(definition, cache):
-    api, vname, vvar, vver = variant_info(definition)
+    api, vname, vvar, vver, guard = variant_info(definition)
 
     assert api not in cache, f"duplicate API {api}"
     assert vvar not in cache, f"duplicate const {vvar}"
     assert int(vver) > 0, f"{vver} missing minimum required valgrind version for {api}"
     cache.update([api, vvar])
 
-    return api, vname, vvar, vver
+    return guard, vname, vvar, vver
 
 
 @dataclass
@@ -71,8 +74,8 @@ class Gen:
         self.r_impls += r_impl_prolog(self.current_tool) + NEWLINE
 
     def append_variant_def(self, definition: str):
-        api, vname, vvar, vver = variant(definition, self.history)
-        self.c_enums += c_enum_variant_def(api, vname, vvar, self.n) + NEWLINE
+        guard, vname, vvar, vver = variant(definition, self.history)
+        self.c_enums += c_enum_variant_def(guard, vname, vvar, self.n) + NEWLINE
         self.r_impls += f"""{TAB}{TAB}{TAB}Self::{vname} => {vver},""" + NEWLINE
         self.n += 1
 
@@ -84,6 +84,9 @@ impl CG_HelgrindClientRequest {
 			Self::CG_ANNOTATE_HAPPENS_BEFORE => 307,
 			Self::CG_ANNOTATE_HAPPENS_AFTER => 307,
 			Self::CG_ANNOTATE_HAPPENS_BEFORE_FORGET_ALL => 307,
+			Self::CG_VALGRIND_HG_DISABLE_CHECKING => 308,
+			Self::CG_VALGRIND_HG_ENABLE_CHECKING => 308,
+			Self::CG_VALGRIND_HG_GET_ABITS => 310,
 		}
 	}
 }
@@ -100,6 +103,12 @@ impl CG_DRDClientRequest {
 			Self::CG_ANNOTATE_IGNORE_WRITES_BEGIN => 305,
 			Self::CG_ANNOTATE_NEW_MEMORY => 305,
 			Self::CG_ANNOTATE_THREAD_NAME => 305,
+			Self::CG_DRD_ANNOTATE_HAPPENS_BEFORE => 305,
+			Self::CG_DRD_ANNOTATE_HAPPENS_AFTER => 305,
+			Self::CG_ANNOTATE_BARRIER_INIT => 306,
+			Self::CG_ANNOTATE_BARRIER_DESTROY => 306,
+			Self::CG_ANNOTATE_BARRIER_WAIT_BEFORE => 306,
+			Self::CG_ANNOTATE_BARRIER_WAIT_AFTER => 306,
 		}
 	}
 }
@@ -321,6 +321,21 @@ typedef enum {
 #else
 	CG_ANNOTATE_HAPPENS_BEFORE_FORGET_ALL = 7,
 #endif
+#ifdef VALGRIND_HG_DISABLE_CHECKING 
+	CG_VALGRIND_HG_DISABLE_CHECKING = _VG_USERREQ__HG_ARANGE_MAKE_UNTRACKED,
+#else
+	CG_VALGRIND_HG_DISABLE_CHECKING = 8,
+#endif
+#ifdef VALGRIND_HG_ENABLE_CHECKING 
+	CG_VALGRIND_HG_ENABLE_CHECKING = _VG_USERREQ__HG_ARANGE_MAKE_TRACKED,
+#else
+	CG_VALGRIND_HG_ENABLE_CHECKING = 9,
+#endif
+#ifdef VALGRIND_HG_GET_ABITS 
+	CG_VALGRIND_HG_GET_ABITS = _VG_USERREQ__HG_GET_ABITS,
+#else
+	CG_VALGRIND_HG_GET_ABITS = 10,
+#endif
 } CG_HelgrindClientRequest;
 
 typedef enum {
@@ -374,6 +389,36 @@ typedef enum {
 #else
 	CG_ANNOTATE_THREAD_NAME = 9,
 #endif
+#ifdef ANNOTATE_HAPPENS_BEFORE
+	CG_DRD_ANNOTATE_HAPPENS_BEFORE = VG_USERREQ__DRD_ANNOTATE_HAPPENS_BEFORE,
+#else
+	CG_DRD_ANNOTATE_HAPPENS_BEFORE = 10,
+#endif
+#ifdef ANNOTATE_HAPPENS_AFTER
+	CG_DRD_ANNOTATE_HAPPENS_AFTER = VG_USERREQ__DRD_ANNOTATE_HAPPENS_AFTER,
+#else
+	CG_DRD_ANNOTATE_HAPPENS_AFTER = 11,
+#endif
+#ifdef ANNOTATE_BARRIER_INIT 
+	CG_ANNOTATE_BARRIER_INIT = VG_USERREQ__DRD_ANNOTATE_BARRIER_INIT,
+#else
+	CG_ANNOTATE_BARRIER_INIT = 12,
+#endif
+#ifdef ANNOTATE_BARRIER_DESTROY 
+	CG_ANNOTATE_BARRIER_DESTROY = VG_USERREQ__DRD_ANNOTATE_BARRIER_DESTROY,
+#else
+	CG_ANNOTATE_BARRIER_DESTROY = 13,
+#endif
+#ifdef ANNOTATE_BARRIER_WAIT_BEFORE 
+	CG_ANNOTATE_BARRIER_WAIT_BEFORE = VG_USERREQ__DRD_ANNOTATE_BARRIER_WAIT_BEFORE,
+#else
+	CG_ANNOTATE_BARRIER_WAIT_BEFORE = 14,
+#endif
+#ifdef ANNOTATE_BARRIER_WAIT_AFTER 
+	CG_ANNOTATE_BARRIER_WAIT_AFTER = VG_USERREQ__DRD_ANNOTATE_BARRIER_WAIT_AFTER,
+#else
+	CG_ANNOTATE_BARRIER_WAIT_AFTER = 15,
+#endif
 } CG_DRDClientRequest;
 
 typedef enum {